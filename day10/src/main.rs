@@ -1,9 +1,159 @@
+use std::collections::HashMap;
+
+use grid::{DenseGrid, Grid};
+use image::{Image, Rgb};
+use vector::IVec;
+
 fn main() {
-    let (max, (x, y)) = max_visibility(PUZZLE_INPUT).unwrap();
-    println!("1. With max visibility we see {} asteroids.", max);
+    let args: Vec<String> = std::env::args().collect();
+    if let Err(err) = run_command(&args) {
+        eprintln!("usage error: {:?}", err);
+        std::process::exit(2);
+    }
+}
+
+fn run_command(args: &[String]) -> Result<(), UsageError> {
+    if args.len() > 1 && args[1] == "vaporize" {
+        let asteroids = parse_asteroids(PUZZLE_INPUT).map_err(UsageError::Field)?;
+        let (x, y) = match top_stations(&visibility_map(&asteroids), 1).first() {
+            Some((_, station)) => *station,
+            None => return Ok(()),
+        };
+        let order = vaporization_order(&asteroids, x, y);
+        let mut csv = String::from("n,rotation,x,y,angle\n");
+        for (n, vaporization) in order.iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{},{:.4}\n",
+                n + 1,
+                vaporization.rotation,
+                vaporization.pos.0,
+                vaporization.pos.1,
+                vaporization.angle
+            );
+        }
+        match args.get(2) {
+            Some(path) => {
+                std::fs::write(path, csv).map_err(|error| UsageError::Io(error.kind()))?;
+                println!("{} vaporizations written to {}", order.len(), path);
+            }
+            None => print!("{}", csv),
+        }
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "heatmap" {
+        let k = parse_arg(args, 2, 5)?;
+        let map = visibility_map(&parse_asteroids(PUZZLE_INPUT).map_err(UsageError::Field)?);
+        print!("{}", heatmap_text(&map));
+        for (rank, (visible, station)) in top_stations(&map, k).iter().enumerate() {
+            println!("{}. {:?} sees {} asteroids", rank + 1, station, visible);
+        }
+        if let Some(path) = args.get(3) {
+            heatmap_image(&map)
+                .with_scale(8)
+                .save(path)
+                .map_err(|error| UsageError::Io(error.kind()))?;
+            println!("heatmap written to {}", path);
+        }
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "generate" {
+        let width = parse_arg(args, 2, 40)?;
+        let height = parse_arg(args, 3, width)?;
+        let density = parse_arg(args, 4, 0.3)?;
+        let seed = parse_arg(args, 5, 1)?;
+        print!("{}", generate_field(width, height, density, seed));
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "field" {
+        let path = args
+            .get(2)
+            .ok_or(UsageError::MissingArgument { position: 2 })?;
+        let input = std::fs::read_to_string(path).map_err(|error| UsageError::Io(error.kind()))?;
+        let field = parse_field(&input).map_err(UsageError::Field)?;
+        let (x, y) = match field.station {
+            Some(station) => station,
+            None => match top_stations(&visibility_map(&field.asteroids), 1).first() {
+                Some((_, station)) => *station,
+                None => {
+                    println!("No asteroid to place a station on.");
+                    return Ok(());
+                }
+            },
+        };
+        println!(
+            "Station at {:?} sees {} asteroids.",
+            (x, y),
+            sonar_around(&field.asteroids, x, y).len()
+        );
+        if let Some(vaporization) = vaporization_order(&field.asteroids, x, y).get(199) {
+            println!(
+                "The 200th destroyed asteroid would be: {:?}",
+                vaporization.pos
+            );
+        }
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "bench" {
+        let size = parse_arg(args, 2, 80)?;
+        let density = parse_arg(args, 3, 0.3)?;
+        let threads = parse_arg(
+            args,
+            4,
+            std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        )?;
+        let field = generate_field(size, size, density, 1);
+        let start = std::time::Instant::now();
+        let sequential = max_visibility(&field).map_err(UsageError::Field)?;
+        let sequential_time = start.elapsed();
+        let start = std::time::Instant::now();
+        let parallel = max_visibility_parallel(&field, threads).map_err(UsageError::Field)?;
+        let parallel_time = start.elapsed();
+        assert_eq!(sequential, parallel);
+        println!("best station: {:?}", sequential);
+        println!("sequential: {:?}", sequential_time);
+        println!(
+            "parallel ({} threads): {:?} ({:.2}x)",
+            threads,
+            parallel_time,
+            sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+        );
+        return Ok(());
+    }
+
+    if let Some((max, (x, y))) = max_visibility(PUZZLE_INPUT).map_err(UsageError::Field)? {
+        println!("1. With max visibility we see {} asteroids.", max);
 
-    let position = guess_nth(PUZZLE_INPUT, x, y, 200).unwrap();
-    println!("2. The 200th destroyed asteroid would be: {:?}", position);
+        if let Some(position) = guess_nth(PUZZLE_INPUT, x, y, 200).map_err(UsageError::Field)? {
+            println!("2. The 200th destroyed asteroid would be: {:?}", position);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum UsageError {
+    MissingArgument { position: usize },
+    InvalidArgument { position: usize, found: String },
+    Field(ParseError),
+    Io(std::io::ErrorKind),
+}
+
+fn parse_arg<T: std::str::FromStr>(
+    args: &[String],
+    position: usize,
+    default: T,
+) -> Result<T, UsageError> {
+    match args.get(position) {
+        Some(arg) => arg.parse().map_err(|_| UsageError::InvalidArgument {
+            position,
+            found: arg.clone(),
+        }),
+        None => Ok(default),
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -12,167 +162,1112 @@ enum Space {
     Asteroid,
 }
 
-fn parse_asteroids(input: &str) -> Vec<Vec<Space>> {
-    input
-        .split('\n')
-        .collect::<Vec<&str>>()
-        .into_iter()
-        .map(|piece| {
-            piece
-                .chars()
-                .filter(|c| *c == '#' || *c == '.')
-                .map(|c| {
-                    if c == '#' {
-                        Space::Asteroid
-                    } else {
-                        Space::Void
+#[derive(PartialEq, Debug, Copy, Clone)]
+enum ParseError {
+    Empty,
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    MultipleStations {
+        first: (usize, usize),
+        second: (usize, usize),
+    },
+}
+
+#[derive(PartialEq, Debug)]
+struct AsteroidField {
+    asteroids: DenseGrid<Space>,
+    station: Option<(usize, usize)>,
+}
+
+const STATION: char = 'X';
+
+fn parse_field(input: &str) -> Result<AsteroidField, ParseError> {
+    let mut rows = vec![];
+    let mut station = None;
+    if input.trim().is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let lines = input.trim_matches(|c| c == '\n' || c == '\r').lines();
+    for (y, line) in lines.enumerate() {
+        let mut row = vec![];
+        for (x, c) in line.chars().enumerate() {
+            row.push(match c {
+                '#' => Space::Asteroid,
+                '.' => Space::Void,
+                STATION => {
+                    if let Some(first) = station {
+                        return Err(ParseError::MultipleStations {
+                            first,
+                            second: (x, y),
+                        });
                     }
+                    station = Some((x, y));
+                    Space::Asteroid
+                }
+                found => {
+                    return Err(ParseError::InvalidCharacter {
+                        line: y,
+                        column: x,
+                        found,
+                    })
+                }
+            });
+        }
+        rows.push(row);
+    }
+    let asteroids = DenseGrid::from_rows(rows).map_err(|ragged| ParseError::RaggedRow {
+        line: ragged.row,
+        expected: ragged.expected,
+        found: ragged.found,
+    })?;
+    Ok(AsteroidField { asteroids, station })
+}
+
+fn parse_asteroids(input: &str) -> Result<DenseGrid<Space>, ParseError> {
+    parse_field(input).map(|field| field.asteroids)
+}
+
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng {
+            state: seed ^ 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+fn generate_field(width: usize, height: usize, density: f64, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut field = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        for _ in 0..width {
+            field.push(if rng.chance(density) { '#' } else { '.' });
+        }
+        field.push('\n');
+    }
+    field
+}
+
+type BestStation = (usize, (usize, usize));
+
+fn max_visibility(input: &str) -> Result<Option<BestStation>, ParseError> {
+    let map = visibility_map(&parse_asteroids(input)?);
+    Ok(top_stations(&map, 1)
+        .into_iter()
+        .find(|(visible, _)| *visible > 0))
+}
+
+fn visibility_map(asteroids: &DenseGrid<Space>) -> DenseGrid<Option<usize>> {
+    asteroids.map(|(x, y), space| match space {
+        Space::Asteroid => Some(sonar_around(asteroids, x as usize, y as usize).len()),
+        Space::Void => None,
+    })
+}
+
+fn max_visibility_parallel(input: &str, threads: usize) -> Result<Option<BestStation>, ParseError> {
+    let map = visibility_map_parallel(&parse_asteroids(input)?, threads);
+    Ok(top_stations(&map, 1)
+        .into_iter()
+        .find(|(visible, _)| *visible > 0))
+}
+
+fn visibility_map_parallel(
+    asteroids: &DenseGrid<Space>,
+    threads: usize,
+) -> DenseGrid<Option<usize>> {
+    let threads = threads.max(1);
+    let stations: Vec<(usize, usize)> = asteroids
+        .cells()
+        .filter(|(_, space)| **space == Space::Asteroid)
+        .map(|((x, y), _)| (x as usize, y as usize))
+        .collect();
+    let counts: HashMap<(usize, usize), usize> = std::thread::scope(|scope| {
+        let stations = &stations;
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || {
+                    stations
+                        .iter()
+                        .skip(worker)
+                        .step_by(threads)
+                        .map(|&(x, y)| ((x, y), sonar_around(asteroids, x, y).len()))
+                        .collect::<Vec<_>>()
                 })
-                .collect::<Vec<Space>>()
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    asteroids.map(|(x, y), _| counts.get(&(x as usize, y as usize)).copied())
+}
+
+fn top_stations(map: &DenseGrid<Option<usize>>, k: usize) -> Vec<(usize, (usize, usize))> {
+    let mut stations: Vec<(usize, (usize, usize))> = map
+        .cells()
+        .filter_map(|((x, y), visible)| visible.map(|visible| (visible, (x as usize, y as usize))))
+        .collect();
+    stations.sort_by_key(|(visible, _)| std::cmp::Reverse(*visible));
+    stations.truncate(k);
+    stations
+}
+
+const SHADES: [char; 9] = ['.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+fn heatmap_text(map: &DenseGrid<Option<usize>>) -> String {
+    let max = map
+        .cells()
+        .filter_map(|(_, visible)| *visible)
+        .max()
+        .unwrap_or(0);
+    map.render(|visible| match visible.copied().flatten() {
+        Some(visible) => SHADES[visible * (SHADES.len() - 1) / max.max(1)],
+        None => ' ',
+    })
+}
+
+fn heatmap_image(map: &DenseGrid<Option<usize>>) -> Image {
+    let max = map
+        .cells()
+        .filter_map(|(_, visible)| *visible)
+        .max()
+        .unwrap_or(0);
+    let pixels: Vec<Rgb> = map
+        .cells()
+        .map(|(_, visible)| match visible {
+            Some(visible) => {
+                let heat = (visible * 255 / max.max(1)) as u8;
+                [heat, 0, 255 - heat]
+            }
+            None => [0, 0, 0],
+        })
+        .collect();
+    Image::from_rgb(map.width(), map.height(), &pixels)
+}
+
+type Direction = (i64, i64);
+
+fn reduce(dx: i64, dy: i64) -> (Direction, i64) {
+    let (direction, steps) = IVec::new(dx, dy).reduced();
+    (direction.into(), steps)
+}
+
+fn half(direction: Direction) -> u8 {
+    if direction.0 > 0 || direction.0 == 0 && direction.1 < 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn clockwise(a: Direction, b: Direction) -> std::cmp::Ordering {
+    half(a)
+        .cmp(&half(b))
+        .then_with(|| IVec::from(b).cross(IVec::from(a)).cmp(&0))
+}
+
+fn lines_of_sight(
+    asteroids: &DenseGrid<Space>,
+    x: usize,
+    y: usize,
+) -> Vec<(Direction, Vec<(usize, usize)>)> {
+    let mut lines = HashMap::new();
+    for ((ax, ay), space) in asteroids.cells() {
+        if *space != Space::Asteroid || (ax, ay) == (x as i64, y as i64) {
+            continue;
+        }
+        let (direction, steps) = reduce(ax - x as i64, ay - y as i64);
+        lines
+            .entry(direction)
+            .or_insert_with(Vec::new)
+            .push((steps, (ax as usize, ay as usize)));
+    }
+    let mut lines: Vec<(Direction, Vec<(usize, usize)>)> = lines
+        .into_iter()
+        .map(|(direction, mut line)| {
+            line.sort();
+            (direction, line.into_iter().map(|(_, pos)| pos).collect())
         })
+        .collect();
+    lines.sort_by(|(a, _), (b, _)| clockwise(*a, *b));
+    lines
+}
+
+fn sonar_around(asteroids: &DenseGrid<Space>, x: usize, y: usize) -> Vec<(usize, usize)> {
+    lines_of_sight(asteroids, x, y)
+        .into_iter()
+        .map(|(_, line)| line[0])
         .collect()
 }
 
-fn max_visibility(input: &str) -> Option<(usize, (usize, usize))> {
-    let asteroids = parse_asteroids(input);
-    let mut result = None;
-    for y in 0..asteroids.len() {
-        for x in 0..asteroids[0].len() {
-            if asteroids[y][x] == Space::Void {
-                continue;
+#[derive(PartialEq, Debug, Copy, Clone)]
+struct Vaporization {
+    pos: (usize, usize),
+    rotation: usize,
+    angle: f64,
+}
+
+fn vaporization_order(asteroids: &DenseGrid<Space>, x: usize, y: usize) -> Vec<Vaporization> {
+    let mut order: Vec<(usize, usize, Vaporization)> = vec![];
+    for (rank, (direction, line)) in lines_of_sight(asteroids, x, y).into_iter().enumerate() {
+        let angle = IVec::from(direction).to_f64().bearing();
+        for (index, pos) in line.into_iter().enumerate() {
+            order.push((
+                index,
+                rank,
+                Vaporization {
+                    pos,
+                    rotation: index + 1,
+                    angle,
+                },
+            ));
+        }
+    }
+    order.sort_by_key(|(index, rank, _)| (*index, *rank));
+    order
+        .into_iter()
+        .map(|(_, _, vaporization)| vaporization)
+        .collect()
+}
+
+fn guess_nth(
+    input: &str,
+    x: usize,
+    y: usize,
+    n: usize,
+) -> Result<Option<(usize, usize)>, ParseError> {
+    let asteroids = parse_asteroids(input)?;
+    if n == 0 {
+        return Ok(None);
+    }
+    Ok(vaporization_order(&asteroids, x, y)
+        .get(n - 1)
+        .map(|vaporization| vaporization.pos))
+}
+
+#[allow(dead_code)]
+mod vector {
+    use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+    pub const EPSILON: f64 = 1e-9;
+
+    pub fn gcd(a: i64, b: i64) -> i64 {
+        if b == 0 {
+            a.abs()
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct MVec {
+        pub x: f64,
+        pub y: f64,
+    }
+
+    impl MVec {
+        pub fn new(x: f64, y: f64) -> Self {
+            MVec { x, y }
+        }
+
+        pub fn zero() -> Self {
+            MVec::new(0.0, 0.0)
+        }
+
+        pub fn from_angle(radians: f64) -> Self {
+            let (sin, cos) = radians.sin_cos();
+            MVec::new(cos, sin)
+        }
+
+        pub fn dot(self, rhs: Self) -> f64 {
+            self.x * rhs.x + self.y * rhs.y
+        }
+
+        pub fn cross(self, rhs: Self) -> f64 {
+            self.x * rhs.y - self.y * rhs.x
+        }
+
+        pub fn length_squared(self) -> f64 {
+            self.dot(self)
+        }
+
+        pub fn length(self) -> f64 {
+            self.length_squared().sqrt()
+        }
+
+        pub fn distance(self, rhs: Self) -> f64 {
+            (self - rhs).length()
+        }
+
+        pub fn normalize(self) -> Option<Self> {
+            let length = self.length();
+            if length < EPSILON {
+                None
+            } else {
+                Some(self / length)
             }
-            let detections = sonar_around(&asteroids, x, y);
-            let max = if let Some((max, _)) = result { max } else { 0 };
-            if detections.len() > max {
-                result = Some((detections.len(), (x, y)));
+        }
+
+        pub fn angle(self) -> f64 {
+            self.y.atan2(self.x)
+        }
+
+        pub fn angle_to(self, rhs: Self) -> f64 {
+            self.cross(rhs).atan2(self.dot(rhs))
+        }
+
+        pub fn bearing(self) -> f64 {
+            let degrees = self.x.atan2(-self.y).to_degrees();
+            if degrees < 0.0 {
+                degrees + 360.0
+            } else {
+                degrees
             }
         }
+
+        pub fn rotate(self, radians: f64) -> Self {
+            let (sin, cos) = radians.sin_cos();
+            MVec::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+        }
+
+        pub fn perpendicular(self) -> Self {
+            MVec::new(-self.y, self.x)
+        }
+
+        pub fn approx_eq(self, rhs: Self, epsilon: f64) -> bool {
+            (self.x - rhs.x).abs() <= epsilon && (self.y - rhs.y).abs() <= epsilon
+        }
     }
-    result
-}
 
-const RAYCASTING_RESOLUTION: f64 = 0.04;
+    impl Mul<f64> for MVec {
+        type Output = MVec;
+        fn mul(mut self, rhs: f64) -> Self::Output {
+            self.x *= rhs;
+            self.y *= rhs;
+            self
+        }
+    }
+
+    impl Div<f64> for MVec {
+        type Output = MVec;
+        fn div(mut self, rhs: f64) -> Self::Output {
+            self.x /= rhs;
+            self.y /= rhs;
+            self
+        }
+    }
+
+    impl Add for MVec {
+        type Output = MVec;
+        fn add(mut self, rhs: Self) -> Self::Output {
+            self += rhs;
+            self
+        }
+    }
+
+    impl Sub for MVec {
+        type Output = MVec;
+        fn sub(mut self, rhs: Self) -> Self::Output {
+            self -= rhs;
+            self
+        }
+    }
+
+    impl Neg for MVec {
+        type Output = MVec;
+        fn neg(self) -> Self::Output {
+            MVec::new(-self.x, -self.y)
+        }
+    }
+
+    impl AddAssign for MVec {
+        fn add_assign(&mut self, rhs: Self) {
+            self.x += rhs.x;
+            self.y += rhs.y;
+        }
+    }
+
+    impl SubAssign for MVec {
+        fn sub_assign(&mut self, rhs: Self) {
+            self.x -= rhs.x;
+            self.y -= rhs.y;
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct IVec {
+        pub x: i64,
+        pub y: i64,
+    }
+
+    impl IVec {
+        pub fn new(x: i64, y: i64) -> Self {
+            IVec { x, y }
+        }
+
+        pub fn zero() -> Self {
+            IVec::new(0, 0)
+        }
+
+        pub fn dot(self, rhs: Self) -> i64 {
+            self.x * rhs.x + self.y * rhs.y
+        }
+
+        pub fn cross(self, rhs: Self) -> i64 {
+            self.x * rhs.y - self.y * rhs.x
+        }
+
+        pub fn manhattan(self) -> i64 {
+            self.x.abs() + self.y.abs()
+        }
 
-const THETA_START: f64 = -180.0 * std::f64::consts::PI * (1.0 / 180.0);
-const THETA_END: f64 = THETA_START + 2.0 * std::f64::consts::PI;
-const THETA_STEP: f64 = RAYCASTING_RESOLUTION * std::f64::consts::PI * (1.0 / 180.0);
+        pub fn signum(self) -> Self {
+            IVec::new(self.x.signum(), self.y.signum())
+        }
 
-fn sonar_around(asteroids: &[Vec<Space>], x: usize, y: usize) -> Vec<(usize, usize)> {
-    let mut theta = THETA_END;
-    let mut detections = vec![];
-    while theta > THETA_START {
-        let (tx, ty) = theta.sin_cos();
-        if let Some(col) = trace_ray(asteroids, x, y, MVec::new(tx, ty)) {
-            if !detections.contains(&col) {
-                detections.push(col);
+        pub fn reduced(self) -> (Self, i64) {
+            let steps = gcd(self.x, self.y);
+            if steps == 0 {
+                (self, 0)
+            } else {
+                (IVec::new(self.x / steps, self.y / steps), steps)
             }
         }
-        theta -= THETA_STEP;
+
+        pub fn perpendicular(self) -> Self {
+            IVec::new(-self.y, self.x)
+        }
+
+        pub fn to_f64(self) -> MVec {
+            MVec::new(self.x as f64, self.y as f64)
+        }
     }
-    detections
-}
 
-const RAY_STEP: f64 = RAYCASTING_RESOLUTION;
-const MIN_DISTANCE: f64 = RAYCASTING_RESOLUTION;
-
-fn trace_ray(
-    asteroids: &[Vec<Space>],
-    origin_x: usize,
-    origin_y: usize,
-    trajectory: MVec,
-) -> Option<(usize, usize)> {
-    let mut ray = from_usize_to_f64(origin_x, origin_y);
-    while ray.y < asteroids.len() as f64
-        && ray.x < asteroids[0].len() as f64
-        && ray.x > 0.0
-        && ray.y > 0.0
-    {
-        let (cell_x, cell_y) = from_f64_to_usize(ray);
-        ray = ray + trajectory * RAY_STEP;
-        if cell_x == origin_x && cell_y == origin_y || asteroids[cell_y][cell_x] != Space::Asteroid
-        {
-            continue;
+    impl From<(i64, i64)> for IVec {
+        fn from((x, y): (i64, i64)) -> Self {
+            IVec::new(x, y)
+        }
+    }
+
+    impl From<IVec> for (i64, i64) {
+        fn from(vec: IVec) -> Self {
+            (vec.x, vec.y)
         }
-        let distance = module(ray - from_usize_to_f64(cell_x, cell_y));
-        if distance <= MIN_DISTANCE {
-            return Some((cell_x, cell_y));
+    }
+
+    impl Mul<i64> for IVec {
+        type Output = IVec;
+        fn mul(mut self, rhs: i64) -> Self::Output {
+            self.x *= rhs;
+            self.y *= rhs;
+            self
+        }
+    }
+
+    impl Add for IVec {
+        type Output = IVec;
+        fn add(mut self, rhs: Self) -> Self::Output {
+            self += rhs;
+            self
+        }
+    }
+
+    impl Sub for IVec {
+        type Output = IVec;
+        fn sub(mut self, rhs: Self) -> Self::Output {
+            self -= rhs;
+            self
+        }
+    }
+
+    impl Neg for IVec {
+        type Output = IVec;
+        fn neg(self) -> Self::Output {
+            IVec::new(-self.x, -self.y)
+        }
+    }
+
+    impl AddAssign for IVec {
+        fn add_assign(&mut self, rhs: Self) {
+            self.x += rhs.x;
+            self.y += rhs.y;
+        }
+    }
+
+    impl SubAssign for IVec {
+        fn sub_assign(&mut self, rhs: Self) {
+            self.x -= rhs.x;
+            self.y -= rhs.y;
         }
     }
-    None
 }
 
-fn guess_nth(input: &str, x: usize, y: usize, n: usize) -> Option<(usize, usize)> {
-    let mut asteroids = parse_asteroids(input);
-    let mut destroyed = 0;
-    loop {
-        let detections = sonar_around(&asteroids, x, y);
-        if detections.is_empty() {
-            break;
-        }
-        for (x, y) in detections {
-            asteroids[y][x] = Space::Void;
-            destroyed += 1;
-            if destroyed == n {
-                return Some((x, y));
+mod grid {
+    use std::collections::HashMap;
+    use std::fmt::Display;
+
+    pub type Pos = (i64, i64);
+
+    #[allow(dead_code)]
+    const NEIGHBORS: [Pos; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    #[allow(dead_code)]
+    const DIAGONAL_NEIGHBORS: [Pos; 8] = [
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+    ];
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub struct Bounds {
+        pub min_x: i64,
+        pub min_y: i64,
+        pub max_x: i64,
+        pub max_y: i64,
+    }
+
+    #[allow(dead_code)]
+    impl Bounds {
+        pub fn new(pos: Pos) -> Self {
+            Bounds {
+                min_x: pos.0,
+                min_y: pos.1,
+                max_x: pos.0,
+                max_y: pos.1,
+            }
+        }
+
+        pub fn include(self, pos: Pos) -> Self {
+            Bounds {
+                min_x: self.min_x.min(pos.0),
+                min_y: self.min_y.min(pos.1),
+                max_x: self.max_x.max(pos.0),
+                max_y: self.max_y.max(pos.1),
+            }
+        }
+
+        pub fn expand(self, margin: i64) -> Self {
+            Bounds {
+                min_x: self.min_x - margin,
+                min_y: self.min_y - margin,
+                max_x: self.max_x + margin,
+                max_y: self.max_y + margin,
             }
         }
+
+        pub fn contains(self, pos: Pos) -> bool {
+            pos.0 >= self.min_x && pos.0 <= self.max_x && pos.1 >= self.min_y && pos.1 <= self.max_y
+        }
+
+        pub fn width(self) -> i64 {
+            self.max_x - self.min_x + 1
+        }
+
+        pub fn height(self) -> i64 {
+            self.max_y - self.min_y + 1
+        }
+
+        pub fn positions(self) -> impl Iterator<Item = Pos> {
+            (self.min_y..=self.max_y)
+                .flat_map(move |y| (self.min_x..=self.max_x).map(move |x| (x, y)))
+        }
     }
-    None
-}
 
-#[derive(Copy, Clone, Debug)]
-struct MVec {
-    x: f64,
-    y: f64,
-}
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub struct RaggedRow {
+        pub row: usize,
+        pub expected: usize,
+        pub found: usize,
+    }
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    #[allow(dead_code)]
+    pub struct OutOfBounds(pub Pos);
+
+    pub trait Grid<T> {
+        fn get(&self, pos: Pos) -> Option<&T>;
+        #[allow(dead_code)]
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T>;
+        fn bounds(&self) -> Option<Bounds>;
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a>;
+
+        #[allow(dead_code)]
+        fn neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn diagonal_neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &DIAGONAL_NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn around(&self, pos: Pos, offsets: &[Pos]) -> Vec<(Pos, &T)> {
+            offsets
+                .iter()
+                .map(|(dx, dy)| (pos.0 + dx, pos.1 + dy))
+                .filter_map(|neighbor| self.get(neighbor).map(|cell| (neighbor, cell)))
+                .collect()
+        }
+
+        fn render<D: Display>(&self, cell: impl Fn(Option<&T>) -> D) -> String {
+            match self.bounds() {
+                Some(bounds) => self.render_region(bounds, cell),
+                None => String::new(),
+            }
+        }
 
-impl MVec {
-    pub fn new(x: f64, y: f64) -> Self {
-        MVec { x, y }
+        fn render_region<D: Display>(
+            &self,
+            bounds: Bounds,
+            cell: impl Fn(Option<&T>) -> D,
+        ) -> String {
+            let mut text = String::new();
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    text += &cell(self.get((x, y))).to_string();
+                }
+                text.push('\n');
+            }
+            text
+        }
     }
-}
 
-impl std::ops::Mul<f64> for MVec {
-    type Output = MVec;
-    fn mul(mut self, rhs: f64) -> Self::Output {
-        self.x *= rhs;
-        self.y *= rhs;
-        self
+    #[derive(Debug, PartialEq, Clone)]
+    #[allow(dead_code)]
+    pub struct SparseGrid<T> {
+        cells: HashMap<Pos, T>,
+        bounds: Option<Bounds>,
     }
-}
 
-impl std::ops::Add for MVec {
-    type Output = MVec;
-    fn add(mut self, rhs: Self) -> Self::Output {
-        self.x += rhs.x;
-        self.y += rhs.y;
-        self
+    #[allow(dead_code)]
+    impl<T> SparseGrid<T> {
+        pub fn new() -> Self {
+            SparseGrid {
+                cells: HashMap::new(),
+                bounds: None,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.cells.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.cells.is_empty()
+        }
+
+        pub fn contains(&self, pos: Pos) -> bool {
+            self.cells.contains_key(&pos)
+        }
+
+        pub fn set(&mut self, pos: Pos, value: T) {
+            self.include(pos);
+            self.cells.insert(pos, value);
+        }
+
+        pub fn get_or_insert_with(&mut self, pos: Pos, value: impl FnOnce() -> T) -> &mut T {
+            self.include(pos);
+            self.cells.entry(pos).or_insert_with(value)
+        }
+
+        fn include(&mut self, pos: Pos) {
+            self.bounds = Some(match self.bounds {
+                Some(bounds) => bounds.include(pos),
+                None => Bounds::new(pos),
+            });
+        }
     }
-}
 
-impl std::ops::Sub for MVec {
-    type Output = MVec;
-    fn sub(mut self, rhs: Self) -> Self::Output {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-        self
+    impl<T> Default for SparseGrid<T> {
+        fn default() -> Self {
+            SparseGrid::new()
+        }
     }
-}
 
-fn from_usize_to_f64(x: usize, y: usize) -> MVec {
-    MVec::new(x as f64 + 0.5, y as f64 + 0.5)
-}
+    impl<T> Grid<T> for SparseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.cells.get(&pos)
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            self.cells.get_mut(&pos)
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            self.bounds
+        }
 
-fn from_f64_to_usize(n: MVec) -> (usize, usize) {
-    (n.x as usize, n.y as usize)
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            Box::new(self.cells.iter().map(|(pos, cell)| (*pos, cell)))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct DenseGrid<T> {
+        width: usize,
+        height: usize,
+        cells: Vec<T>,
+    }
+
+    impl<T> DenseGrid<T> {
+        #[allow(dead_code)]
+        pub fn new(width: usize, height: usize, fill: T) -> Self
+        where
+            T: Clone,
+        {
+            DenseGrid {
+                width,
+                height,
+                cells: vec![fill; width * height],
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, RaggedRow> {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if let Some((row, found)) = rows
+                .iter()
+                .map(Vec::len)
+                .enumerate()
+                .find(|(_, len)| *len != width)
+            {
+                return Err(RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+            Ok(DenseGrid {
+                width,
+                height,
+                cells: rows.into_iter().flatten().collect(),
+            })
+        }
+
+        pub fn map<U>(&self, cell: impl Fn(Pos, &T) -> U) -> DenseGrid<U> {
+            DenseGrid {
+                width: self.width,
+                height: self.height,
+                cells: self.cells().map(|(pos, value)| cell(pos, value)).collect(),
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn set(&mut self, pos: Pos, value: T) -> Result<(), OutOfBounds> {
+            let index = self.index(pos).ok_or(OutOfBounds(pos))?;
+            self.cells[index] = value;
+            Ok(())
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        fn index(&self, pos: Pos) -> Option<usize> {
+            if pos.0 < 0 || pos.1 < 0 || pos.0 >= self.width as i64 || pos.1 >= self.height as i64 {
+                None
+            } else {
+                Some(pos.0 as usize + pos.1 as usize * self.width)
+            }
+        }
+    }
+
+    impl<T> Grid<T> for DenseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.index(pos).map(|index| &self.cells[index])
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            match self.index(pos) {
+                Some(index) => Some(&mut self.cells[index]),
+                None => None,
+            }
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            if self.cells.is_empty() {
+                None
+            } else {
+                Some(Bounds {
+                    min_x: 0,
+                    min_y: 0,
+                    max_x: self.width as i64 - 1,
+                    max_y: self.height as i64 - 1,
+                })
+            }
+        }
+
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            let width = self.width;
+            Box::new(
+                self.cells.iter().enumerate().map(move |(index, cell)| {
+                    (((index % width) as i64, (index / width) as i64), cell)
+                }),
+            )
+        }
+    }
 }
 
-fn module(n: MVec) -> f64 {
-    ((n.x * n.x + n.y * n.y) as f64).sqrt()
+#[allow(dead_code)]
+mod image {
+    use std::io;
+
+    pub type Rgb = [u8; 3];
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Palette {
+        colors: Vec<Rgb>,
+    }
+
+    impl Palette {
+        pub fn new(colors: Vec<Rgb>) -> Self {
+            if colors.is_empty() {
+                panic!("A palette needs at least one color.");
+            }
+            Palette { colors }
+        }
+
+        pub fn monochrome() -> Self {
+            Palette::new(vec![[0, 0, 0], [255, 255, 255]])
+        }
+
+        pub fn len(&self) -> usize {
+            self.colors.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.colors.is_empty()
+        }
+
+        pub fn color(&self, index: usize) -> Rgb {
+            *self
+                .colors
+                .get(index)
+                .unwrap_or_else(|| panic!("No color {} in a palette of {}", index, self.len()))
+        }
+
+        pub fn is_dark(&self, index: usize) -> bool {
+            let [r, g, b] = self.color(index);
+            299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) < 128_000
+        }
+
+        pub fn hex(&self, index: usize) -> String {
+            let [r, g, b] = self.color(index);
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<usize>,
+        scale: usize,
+        palette: Palette,
+    }
+
+    impl Image {
+        pub fn new(width: usize, height: usize, pixels: Vec<usize>) -> Self {
+            if pixels.len() != width * height {
+                panic!(
+                    "{} pixels can't form a {}x{} image.",
+                    pixels.len(),
+                    width,
+                    height
+                );
+            }
+            Image {
+                width,
+                height,
+                pixels,
+                scale: 1,
+                palette: Palette::monochrome(),
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<usize>>) -> Self {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if rows.iter().any(|row| row.len() != width) {
+                panic!("Rows of different length can't form an image.");
+            }
+            Image::new(width, height, rows.into_iter().flatten().collect())
+        }
+
+        pub fn from_bools(rows: &[Vec<bool>]) -> Self {
+            Image::from_rows(
+                rows.iter()
+                    .map(|row| row.iter().map(|set| *set as usize).collect())
+                    .collect(),
+            )
+        }
+
+        pub fn from_rgb(width: usize, height: usize, rgb: &[Rgb]) -> Self {
+            let mut colors: Vec<Rgb> = vec![];
+            let pixels = rgb
+                .iter()
+                .map(
+                    |color| match colors.iter().position(|known| known == color) {
+                        Some(index) => index,
+                        None => {
+                            colors.push(*color);
+                            colors.len() - 1
+                        }
+                    },
+                )
+                .collect();
+            Image::new(width, height, pixels).with_palette(Palette::new(colors))
+        }
+
+        pub fn with_scale(mut self, scale: usize) -> Self {
+            self.scale = scale.max(1);
+            self
+        }
+
+        pub fn with_palette(mut self, palette: Palette) -> Self {
+            self.palette = palette;
+            self
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> Option<usize> {
+            if x < self.width && y < self.height {
+                Some(self.pixels[x + y * self.width])
+            } else {
+                None
+            }
+        }
+
+        fn scaled_size(&self) -> (usize, usize) {
+            (self.width * self.scale, self.height * self.scale)
+        }
+
+        fn scaled(&self, px: usize, py: usize) -> usize {
+            self.pixels[px / self.scale + py / self.scale * self.width]
+        }
+
+        pub fn to_pbm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut pbm = format!("P4\n{} {}\n", width, height).into_bytes();
+            for py in 0..height {
+                let mut row = vec![0u8; width.div_ceil(8)];
+                for px in 0..width {
+                    if self.palette.is_dark(self.scaled(px, py)) {
+                        row[px / 8] |= 0x80 >> (px % 8);
+                    }
+                }
+                pbm.extend(row);
+            }
+            pbm
+        }
+
+        pub fn to_ppm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+            for py in 0..height {
+                for px in 0..width {
+                    ppm.extend_from_slice(&self.palette.color(self.scaled(px, py)));
+                }
+            }
+            ppm
+        }
+
+        pub fn to_svg(&self) -> String {
+            let (width, height) = self.scaled_size();
+            let mut svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">\n",
+                width, height
+            );
+            svg += &format!(
+                "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                width,
+                height,
+                self.palette.hex(0)
+            );
+            for y in 0..self.height {
+                let mut x = 0;
+                while x < self.width {
+                    let index = self.pixels[x + y * self.width];
+                    let run = (x..self.width)
+                        .take_while(|&end| self.pixels[end + y * self.width] == index)
+                        .count();
+                    if index != 0 {
+                        svg += &format!(
+                            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                            x * self.scale,
+                            y * self.scale,
+                            run * self.scale,
+                            self.scale,
+                            self.palette.hex(index)
+                        );
+                    }
+                    x += run;
+                }
+            }
+            svg + "</svg>\n"
+        }
+
+        pub fn save(&self, path: &str) -> io::Result<()> {
+            let bytes = match path.rsplit('.').next() {
+                Some("pbm") => self.to_pbm(),
+                Some("ppm") => self.to_ppm(),
+                Some("svg") => self.to_svg().into_bytes(),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown image format: {}", path),
+                    ))
+                }
+            };
+            std::fs::write(path, bytes)
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::grid::{Bounds, OutOfBounds, Pos, RaggedRow, SparseGrid};
+    use super::vector::{gcd, MVec, EPSILON};
     use super::*;
 
     macro_rules! eq_tests {
@@ -186,71 +1281,266 @@ mod test {
         };
     }
 
+    fn sparse_grid(cells: &[(Pos, char)]) -> SparseGrid<char> {
+        let mut grid = SparseGrid::new();
+        for (pos, cell) in cells {
+            grid.set(*pos, *cell);
+        }
+        grid
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn asteroids(input: &str) -> DenseGrid<Space> {
+        parse_asteroids(input).unwrap()
+    }
+
     fn max_visibility_test(input: &str) -> usize {
-        max_visibility(input).unwrap().0
+        max_visibility(input).unwrap().unwrap().0
     }
 
+    fn random_vectors(count: usize, seed: u64) -> Vec<MVec> {
+        let mut rng = Rng::new(seed);
+        let mut coordinate = move || (rng.next_u64() % 2001) as f64 / 10.0 - 100.0;
+        (0..count)
+            .map(|_| MVec::new(coordinate(), coordinate()))
+            .collect()
+    }
+
+    fn random_pairs(count: usize, seed: u64) -> Vec<(MVec, MVec)> {
+        let vectors = random_vectors(count * 2, seed);
+        vectors.chunks(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+
+    fn random_ivecs(count: usize, seed: u64) -> Vec<IVec> {
+        let mut rng = Rng::new(seed);
+        let mut coordinate = move || (rng.next_u64() % 2001) as i64 - 1000;
+        (0..count)
+            .map(|_| IVec::new(coordinate(), coordinate()))
+            .collect()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-6 * (1.0 + a.abs().max(b.abs()))
+    }
+
+    const EXAMPLE_1: &str = ".#..#
+.....
+#####
+....#
+...##";
+
+    const EXAMPLE_5: &str = ".#..##.###...#######
+##.############..##.
+.#.######.########.#
+.###.#######.####.#.
+#####.##.#.##.###.##
+..#####..#.#########
+####################
+#.####....###.#.#.##
+##.#################
+#####.##.###..####..
+..######..##.#######
+####.##.####...##..#
+.#####..#.######.###
+##...#.##########...
+#.##########.#######
+.####.#.###.###.#.##
+....##.##.###..#####
+.#.#.###########.###
+#.#.#.#####.####.###
+###.##.####.##.#..##";
+
     eq_tests! {
-        simple_parsing_1: parse_asteroids("#.\n##") => vec![vec![Space::Asteroid,Space::Void],vec![Space::Asteroid,Space::Asteroid]];
-        simple_parsing_2: parse_asteroids("..\n##") => vec![vec![Space::Void,Space::Void],vec![Space::Asteroid,Space::Asteroid]];
-        example_1: max_visibility_test(".#..#
-        .....
-        #####
-        ....#
+        parse_field_station: parse_field(".#\n#X") => Ok(AsteroidField { asteroids: DenseGrid::from_rows(vec![vec![Space::Void, Space::Asteroid], vec![Space::Asteroid, Space::Asteroid]]).unwrap(), station: Some((1, 1)) });
+        parse_field_without_station: parse_field(".#\n##").map(|field| field.station) => Ok(None);
+        parse_field_surrounding_blank_lines: parse_field("\n.#\r\n##\n\n").map(|field| field.asteroids) => Ok(asteroids(".#\n##"));
+        parse_field_leading_whitespace: parse_field("  .#\n  ##") => Err(ParseError::InvalidCharacter { line: 0, column: 0, found: ' ' });
+        parse_field_inner_whitespace: parse_field(".#\n# #.") => Err(ParseError::InvalidCharacter { line: 1, column: 1, found: ' ' });
+        parse_field_trailing_whitespace: parse_field(".#\n## ") => Err(ParseError::InvalidCharacter { line: 1, column: 2, found: ' ' });
+        parse_field_invalid_character: parse_field(".#\n#o") => Err(ParseError::InvalidCharacter { line: 1, column: 1, found: 'o' });
+        parse_field_ragged_row: parse_field(".#.\n##\n...") => Err(ParseError::RaggedRow { line: 1, expected: 3, found: 2 });
+        parse_field_blank_row: parse_field(".#\n\n##") => Err(ParseError::RaggedRow { line: 1, expected: 2, found: 0 });
+        parse_field_multiple_stations: parse_field("X.\n.X") => Err(ParseError::MultipleStations { first: (0, 0), second: (1, 1) });
+        parse_field_empty: parse_field(" \n ") => Err(ParseError::Empty);
+        generate_field_size: asteroids(&generate_field(30, 20, 0.3, 7)).cells().count() => 600;
+        generate_field_shape: { let asteroids = asteroids(&generate_field(30, 20, 0.3, 7)); (asteroids.width(), asteroids.height()) } => (30, 20);
+        generate_field_deterministic: generate_field(30, 20, 0.3, 7) => generate_field(30, 20, 0.3, 7);
+        generate_field_seeded: generate_field(30, 20, 0.3, 7) == generate_field(30, 20, 0.3, 8) => false;
+        generate_field_density: { let count = generate_field(100, 100, 0.3, 1).matches('#').count(); count > 2700 && count < 3300 } => true;
+        generate_field_empty: generate_field(5, 5, 0.0, 3).matches('#').count() => 0;
+        generate_field_full: max_visibility(&generate_field(5, 5, 1.0, 3)) => Ok(Some((18, (1, 1))));
+        parallel_example_5: max_visibility_parallel(EXAMPLE_5, 4) => max_visibility(EXAMPLE_5);
+        parallel_part_1: max_visibility_parallel(PUZZLE_INPUT, 3) => Ok(Some((230, (19, 11))));
+        parallel_single_thread: max_visibility_parallel(EXAMPLE_1, 1) => Ok(Some((8, (3, 4))));
+        parallel_zero_threads: max_visibility_parallel(EXAMPLE_1, 0) => Ok(Some((8, (3, 4))));
+        parallel_more_threads_than_asteroids: max_visibility_parallel(EXAMPLE_1, 64) => Ok(Some((8, (3, 4))));
+        parallel_lonely: max_visibility_parallel("..#..", 2) => Ok(None);
+        parallel_map_matches_sequential: (1..=5).map(|seed| { let asteroids = asteroids(&generate_field(25, 25, 0.4, seed)); visibility_map_parallel(&asteroids, 4) == visibility_map(&asteroids) }).collect::<Vec<_>>() => vec![true; 5];
+        parallel_ties_match_sequential: max_visibility_parallel(&generate_field(6, 6, 1.0, 1), 5) => max_visibility(&generate_field(6, 6, 1.0, 1));
+        simple_parsing_1: parse_asteroids("#.\n##") => Ok(DenseGrid::from_rows(vec![vec![Space::Asteroid,Space::Void],vec![Space::Asteroid,Space::Asteroid]]).unwrap());
+        simple_parsing_2: parse_asteroids("..\n##") => Ok(DenseGrid::from_rows(vec![vec![Space::Void,Space::Void],vec![Space::Asteroid,Space::Asteroid]]).unwrap());
+        parse_asteroids_invalid: parse_asteroids("#.\n#?") => Err(ParseError::InvalidCharacter { line: 1, column: 1, found: '?' });
+        render_asteroids: asteroids(".#\n#.").render(|space| if space == Some(&Space::Asteroid) { '#' } else { '.' }) => ".#\n#.\n";
+        grid_bounds_include: Bounds::new((0, 0)).include((3, -2)).include((-1, 1)) => Bounds { min_x: -1, min_y: -2, max_x: 3, max_y: 1 };
+        grid_bounds_size: (Bounds::new((0, 0)).include((3, -2)).width(), Bounds::new((0, 0)).include((3, -2)).height()) => (4, 3);
+        grid_bounds_positions: Bounds::new((0, 0)).include((1, 1)).positions().collect::<Vec<_>>() => vec![(0, 0), (1, 0), (0, 1), (1, 1)];
+        grid_sparse_bounds: sparse_grid(&[((2, -3), 'a'), ((-4, 5), 'b')]).bounds() => Some(Bounds { min_x: -4, min_y: -3, max_x: 2, max_y: 5 });
+        grid_sparse_empty_bounds: SparseGrid::<char>::new().bounds() => None;
+        grid_sparse_neighbors: sparse_grid(&[((0, 0), 'a'), ((1, 0), 'b'), ((1, 1), 'c'), ((0, -1), 'd')]).neighbors((0, 0)) => vec![((0, -1), &'d'), ((1, 0), &'b')];
+        grid_sparse_diagonal_neighbors: sparse_grid(&[((0, 0), 'a'), ((1, 0), 'b'), ((1, 1), 'c')]).diagonal_neighbors((0, 0)) => vec![((1, 0), &'b'), ((1, 1), &'c')];
+        grid_sparse_render: sparse_grid(&[((0, 0), 'a'), ((2, 1), 'b')]).render(|cell| *cell.unwrap_or(&'.')) => "a..\n..b\n";
+        grid_dense_from_rows: DenseGrid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap().get((2, 1)) => Some(&6);
+        grid_dense_out_of_bounds: DenseGrid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap().get((3, 0)) => None;
+        grid_dense_set: { let mut grid = DenseGrid::new(2, 2, 0); grid.set((1, 0), 7).unwrap(); grid.cells().map(|(_, cell)| *cell).collect::<Vec<_>>() } => vec![0, 7, 0, 0];
+        grid_dense_set_out_of_bounds: DenseGrid::new(2, 2, 0).set((2, 0), 7) => Err(OutOfBounds((2, 0)));
+        grid_dense_ragged_rows: DenseGrid::from_rows(vec![vec![1, 2], vec![3]]) => Err(RaggedRow { row: 1, expected: 2, found: 1 });
+        grid_dense_map: DenseGrid::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap().map(|(x, y), cell| cell * 10 + x + y) => DenseGrid::from_rows(vec![vec![10, 21], vec![31, 42]]).unwrap();
+        grid_dense_cells_positions: DenseGrid::new(2, 2, 0).cells().map(|(pos, _)| pos).collect::<Vec<_>>() => vec![(0, 0), (1, 0), (0, 1), (1, 1)];
+        grid_dense_render: DenseGrid::from_rows(vec![vec![1, 0], vec![0, 1]]).unwrap().render(|cell| if cell == Some(&1) { '#' } else { '.' }) => "#.\n.#\n";
+
+        example_1: max_visibility_test(".#..#\n\
+        .....\n\
+        #####\n\
+        ....#\n\
         ...##") => 8;
-        example_2: max_visibility_test("......#.#.
-        #..#.#....
-        ..#######.
-        .#.#.###..
-        .#..#.....
-        ..#....#.#
-        #..#....#.
-        .##.#..###
-        ##...#..#.
+        example_2: max_visibility_test("......#.#.\n\
+        #..#.#....\n\
+        ..#######.\n\
+        .#.#.###..\n\
+        .#..#.....\n\
+        ..#....#.#\n\
+        #..#....#.\n\
+        .##.#..###\n\
+        ##...#..#.\n\
         .#....####") => 33;
-        example_3: max_visibility_test("#.#...#.#.
-        .###....#.
-        .#....#...
-        ##.#.#.#.#
-        ....#.#.#.
-        .##..###.#
-        ..#...##..
-        ..##....##
-        ......#...
+        example_3: max_visibility_test("#.#...#.#.\n\
+        .###....#.\n\
+        .#....#...\n\
+        ##.#.#.#.#\n\
+        ....#.#.#.\n\
+        .##..###.#\n\
+        ..#...##..\n\
+        ..##....##\n\
+        ......#...\n\
         .####.###.") => 35;
-        example_4: max_visibility_test(".#..#..###
-        ####.###.#
-        ....###.#.
-        ..###.##.#
-        ##.##.#.#.
-        ....###..#
-        ..#.#..#.#
-        #..#.#.###
-        .##...##.#
+        example_4: max_visibility_test(".#..#..###\n\
+        ####.###.#\n\
+        ....###.#.\n\
+        ..###.##.#\n\
+        ##.##.#.#.\n\
+        ....###..#\n\
+        ..#.#..#.#\n\
+        #..#.#.###\n\
+        .##...##.#\n\
         .....#.#..") => 41;
-        example_5: max_visibility_test(".#..##.###...#######
-        ##.############..##.
-        .#.######.########.#
-        .###.#######.####.#.
-        #####.##.#.##.###.##
-        ..#####..#.#########
-        ####################
-        #.####....###.#.#.##
-        ##.#################
-        #####.##.###..####..
-        ..######..##.#######
-        ####.##.####...##..#
-        .#####..#.######.###
-        ##...#.##########...
-        #.##########.#######
-        .####.#.###.###.#.##
-        ....##.##.###..#####
-        .#.#.###########.###
-        #.#.#.#####.####.###
+        example_5: max_visibility_test(".#..##.###...#######\n\
+        ##.############..##.\n\
+        .#.######.########.#\n\
+        .###.#######.####.#.\n\
+        #####.##.#.##.###.##\n\
+        ..#####..#.#########\n\
+        ####################\n\
+        #.####....###.#.#.##\n\
+        ##.#################\n\
+        #####.##.###..####..\n\
+        ..######..##.#######\n\
+        ####.##.####...##..#\n\
+        .#####..#.######.###\n\
+        ##...#.##########...\n\
+        #.##########.#######\n\
+        .####.#.###.###.#.##\n\
+        ....##.##.###..#####\n\
+        .#.#.###########.###\n\
+        #.#.#.#####.####.###\n\
         ###.##.####.##.#..##") => 210;
         part_1: max_visibility_test(PUZZLE_INPUT) => 230;
-        sonar_around_clockwise: sonar_around(&parse_asteroids("###
-        ###
+        part_1_station: max_visibility(PUZZLE_INPUT) => Ok(Some((230, (19, 11))));
+        example_5_station: max_visibility(EXAMPLE_5).map(|best| best.map(|(_, station)| station)) => Ok(Some((11, 13)));
+        example_5_guess_1: guess_nth(EXAMPLE_5, 11, 13, 1) => Ok(Some((11, 12)));
+        example_5_guess_2: guess_nth(EXAMPLE_5, 11, 13, 2) => Ok(Some((12, 1)));
+        example_5_guess_3: guess_nth(EXAMPLE_5, 11, 13, 3) => Ok(Some((12, 2)));
+        example_5_guess_10: guess_nth(EXAMPLE_5, 11, 13, 10) => Ok(Some((12, 8)));
+        example_5_guess_20: guess_nth(EXAMPLE_5, 11, 13, 20) => Ok(Some((16, 0)));
+        example_5_guess_50: guess_nth(EXAMPLE_5, 11, 13, 50) => Ok(Some((16, 9)));
+        example_5_guess_100: guess_nth(EXAMPLE_5, 11, 13, 100) => Ok(Some((10, 16)));
+        example_5_guess_199: guess_nth(EXAMPLE_5, 11, 13, 199) => Ok(Some((9, 6)));
+        example_5_guess_200: guess_nth(EXAMPLE_5, 11, 13, 200) => Ok(Some((8, 2)));
+        example_5_guess_201: guess_nth(EXAMPLE_5, 11, 13, 201) => Ok(Some((10, 9)));
+        example_5_guess_299: guess_nth(EXAMPLE_5, 11, 13, 299) => Ok(Some((11, 1)));
+        example_5_guess_too_many: guess_nth(EXAMPLE_5, 11, 13, 300) => Ok(None);
+        part_2: guess_nth(PUZZLE_INPUT, 19, 11, 200) => Ok(Some((12, 5)));
+        visibility_map_example_1: visibility_map(&asteroids(EXAMPLE_1)).render(|visible| visible.copied().flatten().map_or('.'.to_string(), |visible| visible.to_string())) => ".7..7\n.....\n67775\n....7\n...87\n";
+        top_stations_example_1: top_stations(&visibility_map(&asteroids(EXAMPLE_1)), 3) => vec![(8, (3, 4)), (7, (1, 0)), (7, (4, 0))];
+        top_stations_more_than_asteroids: top_stations(&visibility_map(&asteroids(EXAMPLE_1)), 20).len() => 10;
+        top_stations_example_5: top_stations(&visibility_map(&asteroids(EXAMPLE_5)), 1) => vec![(210, (11, 13))];
+        vaporize_unwritable_path: run_command(&args(&["day10", "vaporize", "/nonexistent/vaporized.csv"])) => Err(UsageError::Io(std::io::ErrorKind::NotFound));
+        parse_arg_default: parse_arg(&args(&["day10", "heatmap"]), 2, 5) => Ok(5);
+        parse_arg_invalid: parse_arg::<usize>(&args(&["day10", "heatmap", "five"]), 2, 5) => Err(UsageError::InvalidArgument { position: 2, found: "five".to_string() });
+        heatmap_unwritable_path: run_command(&args(&["day10", "heatmap", "1", "/nonexistent/heatmap.ppm"])) => Err(UsageError::Io(std::io::ErrorKind::NotFound));
+        generate_invalid_density: run_command(&args(&["day10", "generate", "10", "10", "dense"])) => Err(UsageError::InvalidArgument { position: 4, found: "dense".to_string() });
+        field_missing_path: run_command(&args(&["day10", "field"])) => Err(UsageError::MissingArgument { position: 2 });
+        field_unreadable_path: run_command(&args(&["day10", "field", "/nonexistent/field.txt"])) => Err(UsageError::Io(std::io::ErrorKind::NotFound));
+        field_invalid_field: { let path = std::env::temp_dir().join("day10_invalid_field.txt"); std::fs::write(&path, ".#\n# #\n").unwrap(); run_command(&args(&["day10", "field", path.to_str().unwrap()])) } => Err(UsageError::Field(ParseError::InvalidCharacter { line: 1, column: 1, found: ' ' }));
+        bench_invalid_threads: run_command(&args(&["day10", "bench", "4", "0.5", "-1"])) => Err(UsageError::InvalidArgument { position: 4, found: "-1".to_string() });
+        max_visibility_invalid_field: max_visibility("#.\n#") => Err(ParseError::RaggedRow { line: 1, expected: 2, found: 1 });
+        guess_nth_invalid_field: guess_nth("#o", 0, 0, 1) => Err(ParseError::InvalidCharacter { line: 0, column: 1, found: 'o' });
+        max_visibility_lonely: max_visibility("..#..") => Ok(None);
+        heatmap_text_example_1: heatmap_text(&visibility_map(&asteroids(EXAMPLE_1))) => " %  %\n     \n#%%%*\n    %\n   @%\n";
+        heatmap_text_lonely: heatmap_text(&visibility_map(&asteroids(".#"))) => " .\n";
+        heatmap_image_pixels: heatmap_image(&visibility_map(&asteroids("##.\n..#"))).to_ppm()[11..].to_vec() => vec![255, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0];
+        heatmap_image_size: { let image = heatmap_image(&visibility_map(&asteroids(EXAMPLE_5))); (image.width(), image.height()) } => (20, 20);
+        guess_nth_zero: guess_nth(EXAMPLE_5, 11, 13, 0) => Ok(None);
+        vaporization_order_len: vaporization_order(&asteroids(EXAMPLE_5), 11, 13).len() => 299;
+        vaporization_order_rotations: { let order = vaporization_order(&asteroids(EXAMPLE_5), 11, 13); (order[0].rotation, order[199].rotation, order[298].rotation) } => (1, 1, 12);
+        vaporization_order_first_angles: vaporization_order(&asteroids(EXAMPLE_5), 11, 13).iter().take(2).map(|vaporization| (vaporization.pos, (vaporization.angle * 100.0).round() / 100.0)).collect::<Vec<_>>() => vec![((11, 12), 0.0), ((12, 1), 4.76)];
+        vaporization_order_matches_guess_nth: vaporization_order(&asteroids(EXAMPLE_5), 11, 13).iter().map(|vaporization| vaporization.pos).collect::<Vec<_>>() => (1..=299).map(|n| guess_nth(EXAMPLE_5, 11, 13, n).unwrap().unwrap()).collect::<Vec<_>>();
+        vaporization_order_angles_per_rotation: vaporization_order(&asteroids(EXAMPLE_5), 11, 13).windows(2).all(|pair| pair[0].rotation < pair[1].rotation || pair[0].angle < pair[1].angle) => true;
+        vaporization_order_cross: vaporization_order(&asteroids(".#.\n#.#\n.#.\n.#."), 1, 1).iter().map(|vaporization| (vaporization.pos, vaporization.rotation, vaporization.angle)).collect::<Vec<_>>() => vec![((1, 0), 1, 0.0), ((2, 1), 1, 90.0), ((1, 2), 1, 180.0), ((0, 1), 1, 270.0), ((1, 3), 2, 180.0)];
+        bearing_quadrants: [(0.0, -1.0), (1.0, -1.0), (1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (-1.0, -1.0)].iter().map(|(x, y)| MVec::new(*x, *y).bearing()).collect::<Vec<_>>() => vec![0.0, 45.0, 90.0, 180.0, 270.0, 315.0];
+        vector_dot: MVec::new(1.0, 2.0).dot(MVec::new(3.0, -4.0)) => -5.0;
+        vector_cross: MVec::new(1.0, 2.0).cross(MVec::new(3.0, -4.0)) => -10.0;
+        vector_length: MVec::new(3.0, -4.0).length() => 5.0;
+        vector_normalize: MVec::new(3.0, -4.0).normalize() => Some(MVec::new(0.6, -0.8));
+        vector_normalize_zero: MVec::zero().normalize() => None;
+        vector_angle: MVec::new(0.0, 2.0).angle() => std::f64::consts::FRAC_PI_2;
+        vector_angle_to: MVec::new(1.0, 0.0).angle_to(MVec::new(0.0, -1.0)) => -std::f64::consts::FRAC_PI_2;
+        vector_rotate: MVec::new(1.0, 0.0).rotate(std::f64::consts::FRAC_PI_2).approx_eq(MVec::new(0.0, 1.0), EPSILON) => true;
+        vector_from_angle: MVec::from_angle(std::f64::consts::PI).approx_eq(MVec::new(-1.0, 0.0), EPSILON) => true;
+        vector_ops: { let mut vec = MVec::new(1.0, 2.0); vec += MVec::new(3.0, 4.0); vec -= MVec::new(0.5, 0.5); (-vec / 2.0) * 4.0 } => MVec::new(-7.0, -11.0);
+        vector_approx_eq: (MVec::new(1.0, 1.0).approx_eq(MVec::new(1.0 + 1e-12, 1.0), EPSILON), MVec::new(1.0, 1.0).approx_eq(MVec::new(1.001, 1.0), EPSILON)) => (true, false);
+        vector_distance: MVec::new(1.0, 1.0).distance(MVec::new(4.0, 5.0)) => 5.0;
+        ivec_ops: { let mut vec = IVec::new(1, 2); vec += IVec::new(3, 4); vec -= IVec::new(1, 1); -vec * 2 } => IVec::new(-6, -10);
+        ivec_products: (IVec::new(2, 3).dot(IVec::new(4, -1)), IVec::new(2, 3).cross(IVec::new(4, -1))) => (5, -14);
+        ivec_manhattan: IVec::new(-3, 4).manhattan() => 7;
+        ivec_signum: IVec::new(-3, 0).signum() => IVec::new(-1, 0);
+        ivec_reduced: IVec::new(-6, 9).reduced() => (IVec::new(-2, 3), 3);
+        ivec_reduced_zero: IVec::zero().reduced() => (IVec::zero(), 0);
+        ivec_tuple_round_trip: <(i64, i64)>::from(IVec::from((4, -2))) => (4, -2);
+        ivec_to_f64: IVec::new(4, -2).to_f64() => MVec::new(4.0, -2.0);
+        property_rotate_preserves_length: random_pairs(200, 1).iter().all(|(vec, other)| close(vec.rotate(other.x).length(), vec.length())) => true;
+        property_rotate_inverse: random_pairs(200, 2).iter().all(|(vec, other)| vec.rotate(other.y).rotate(-other.y).approx_eq(*vec, 1e-9)) => true;
+        property_angle_to_rotates_onto: random_pairs(200, 3).iter().all(|(a, b)| a.rotate(a.angle_to(*b)).normalize().unwrap().approx_eq(b.normalize().unwrap(), 1e-9)) => true;
+        property_dot_symmetric: random_pairs(200, 4).iter().all(|(a, b)| a.dot(*b) == b.dot(*a)) => true;
+        property_cross_antisymmetric: random_pairs(200, 5).iter().all(|(a, b)| a.cross(*b) == -b.cross(*a)) => true;
+        property_lagrange_identity: random_pairs(200, 6).iter().all(|(a, b)| close(a.dot(*b).powi(2) + a.cross(*b).powi(2), a.length_squared() * b.length_squared())) => true;
+        property_normalize_unit_length: random_vectors(200, 7).iter().filter_map(|vec| vec.normalize()).all(|unit| close(unit.length(), 1.0)) => true;
+        property_perpendicular_orthogonal: random_vectors(200, 8).iter().all(|vec| vec.dot(vec.perpendicular()) == 0.0 && close(vec.cross(vec.perpendicular()), vec.length_squared())) => true;
+        property_add_sub_inverse: random_pairs(200, 9).iter().all(|(a, b)| (*a + *b - *b).approx_eq(*a, 1e-9)) => true;
+        property_mul_div_inverse: random_pairs(200, 10).iter().filter(|(_, b)| b.x != 0.0).all(|(a, b)| (*a * b.x / b.x).approx_eq(*a, 1e-9)) => true;
+        property_neg: random_vectors(200, 11).iter().all(|vec| -(-*vec) == *vec && (*vec + -*vec) == MVec::zero()) => true;
+        property_ivec_matches_mvec: random_ivecs(200, 12).windows(2).all(|pair| pair[0].dot(pair[1]) as f64 == pair[0].to_f64().dot(pair[1].to_f64()) && pair[0].cross(pair[1]) as f64 == pair[0].to_f64().cross(pair[1].to_f64())) => true;
+        property_ivec_reduced: random_ivecs(200, 13).iter().all(|vec| { let (direction, steps) = vec.reduced(); direction * steps == *vec && (steps == 0 || gcd(direction.x, direction.y) == 1) }) => true;
+        property_ivec_triangle_inequality: random_ivecs(200, 14).windows(2).all(|pair| (pair[0] + pair[1]).manhattan() <= pair[0].manhattan() + pair[1].manhattan()) => true;
+        gcd_values: (gcd(12, 18), gcd(-4, 6), gcd(0, -5), gcd(7, 0)) => (6, 2, 5, 7);
+        reduce_direction: reduce(-6, 9) => ((-2, 3), 3);
+        clockwise_order: { let mut directions = vec![(-1, -1), (0, 1), (1, 0), (-1, 0), (1, -1), (0, -1), (-1, 1), (1, 1), (2, -1)]; directions.sort_by(|a, b| clockwise(*a, *b)); directions } => vec![(0, -1), (1, -1), (2, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+        sonar_around_blocked: sonar_around(&asteroids("#.#.#"), 0, 0) => vec![(2, 0)];
+        sonar_around_far_asteroid: sonar_around(&asteroids(&format!("#{}\n{}#", ".".repeat(299), ".".repeat(299))), 0, 0) => vec![(299, 1)];
+        sonar_around_clockwise: sonar_around(&asteroids("###\n\
+        ###\n\
         ###"), 1, 1).into_iter().collect::<Vec<_>>() => vec![(1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2), (0, 1), (0, 0)];
     }
 }
@@ -1,87 +1,491 @@
 use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+use grid::{Bounds, Grid, Pos, SparseGrid};
+use image::Image;
 
 fn main() {
-    let (panels, _) = run_robot(PUZZLE_INPUT.to_vec(), Color::Black);
-    println!("1. panels: {}", panels.len());
+    let args: Vec<String> = std::env::args().collect();
+    if let Err(err) = run_command(&args) {
+        eprintln!("usage error: {:?}", err);
+        std::process::exit(2);
+    }
+}
 
-    let (mut panels, (min_x, min_y, max_x, max_y)) = run_robot(PUZZLE_INPUT.to_vec(), Color::White);
-    println!("2. register code:");
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let pos = (x, y);
-            if !panels.contains_key(&pos) {
-                panels.insert(pos, Panel::new(Color::Black));
+fn run_command(args: &[String]) -> Result<(), UsageError> {
+    if args.len() > 1 && (args[1] == "replay" || args[1] == "frames") {
+        let initial_color = parse_color_arg(args, 2, Color::White)?;
+        let recorded = run_robot(PUZZLE_INPUT.to_vec(), initial_color);
+        let run = run_controller(
+            &mut ReplayController::from_steps(&recorded.steps),
+            initial_color,
+        );
+        if args[1] == "replay" {
+            let delay = parse_arg(args, 3, 20)?;
+            let stdout = std::io::stdout();
+            run.play_ansi(&mut stdout.lock(), std::time::Duration::from_millis(delay))
+                .unwrap();
+        } else {
+            let dir = args.get(3).map_or("frames", String::as_str);
+            let every = parse_arg(args, 4, 1)?;
+            let written = run.export_ppm_frames(dir, every, 4).unwrap();
+            println!("{} frames written to {}", written, dir);
+        }
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "rewind" {
+        let mut controller = IntcodeController::new(PUZZLE_INPUT.to_vec()).with_journal();
+        let run = run_controller(&mut controller, Color::White);
+        let computer = &mut controller.computer;
+        println!(
+            "{} robot steps, {} instructions journaled",
+            run.steps.len(),
+            computer.journal_len()
+        );
+        if args.get(2).map(String::as_str) == Some("written") {
+            let address = parse_arg(args, 3, 0)?;
+            match computer.step_back_until_written(address) {
+                Some(steps) => println!(
+                    "rewound {} instructions to the last write of {}",
+                    steps, address
+                ),
+                None => println!("{} was never written", address),
             }
-            print!(
-                "{}",
-                match panels[&pos].color {
-                    Color::Black => ' ',
-                    Color::White => '*',
-                }
+        } else {
+            let steps = parse_arg(args, 2, 1)?;
+            match computer.step_back(steps) {
+                Ok(()) => println!("rewound {} instructions", steps),
+                Err(err) => println!("rewind stopped early: {:?}", err),
+            }
+        }
+        println!(
+            "ptr: {} relative base: {} instructions left in journal: {}",
+            computer.ptr,
+            computer.relative_base,
+            computer.journal_len()
+        );
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "export" {
+        let path = args.get(2).map_or("registration.svg", String::as_str);
+        let scale = parse_arg(args, 3, 10)?;
+        run_robot(PUZZLE_INPUT.to_vec(), Color::White)
+            .image()
+            .with_scale(scale)
+            .save(path)
+            .unwrap();
+        println!("registration code written to {}", path);
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "stats" {
+        let initial_color = parse_color_arg(args, 2, Color::Black)?;
+        print!(
+            "{}",
+            run_robot(PUZZLE_INPUT.to_vec(), initial_color).report()
+        );
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "fleet" {
+        let count = parse_arg(args, 2, 2)?;
+        let fleet = (0..count)
+            .map(|index| {
+                FleetRobot::intcode(
+                    PUZZLE_INPUT.to_vec(),
+                    (index as i64 * 20, 0),
+                    Direction::Up,
+                    Color::Black,
+                )
+            })
+            .collect();
+        let run = run_fleet(fleet);
+        print!("{}", run.stats);
+        for (index, robot) in run.robots.iter().enumerate() {
+            println!(
+                "robot {}: {} steps, ends on {:?}",
+                index,
+                run.steps
+                    .iter()
+                    .filter(|(owner, _)| *owner == index)
+                    .count(),
+                robot.pos
+            );
+        }
+        for conflict in &run.conflicts {
+            println!(
+                "conflict after step {}: robots {:?} on {:?}",
+                conflict.step, conflict.robots, conflict.pos
             );
         }
-        println!();
+        print!(
+            "{}",
+            run.panels
+                .render_region(run.bounds, |panel| match panel.map(|panel| panel.color) {
+                    Some(Color::White) => '*',
+                    _ => ' ',
+                })
+        );
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "ant" {
+        let steps = parse_arg(args, 2, 11000)?;
+        let run = run_controller(&mut RuleTableController::langtons_ant(steps), Color::Black);
+        print!(
+            "{}",
+            run.panels
+                .render_region(run.bounds, |panel| match panel.map(|panel| panel.color) {
+                    Some(Color::White) => '*',
+                    _ => ' ',
+                })
+        );
+        return Ok(());
+    }
+
+    let run = run_robot(PUZZLE_INPUT.to_vec(), Color::Black);
+    println!("1. panels: {}", run.panels.len());
+
+    let run = run_robot(PUZZLE_INPUT.to_vec(), Color::White);
+    println!("2. register code:");
+    print!(
+        "{}",
+        run.panels
+            .render_region(run.bounds, |panel| match panel.map(|panel| panel.color) {
+                Some(Color::White) => '*',
+                _ => ' ',
+            })
+    );
+    println!("   reads: {}", ocr_panels(&run.panels).unwrap_or_default());
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum UsageError {
+    InvalidArgument { position: usize, found: String },
+    UnknownColor(String),
+}
+
+fn parse_arg<T: std::str::FromStr>(
+    args: &[String],
+    position: usize,
+    default: T,
+) -> Result<T, UsageError> {
+    match args.get(position) {
+        Some(arg) => arg.parse().map_err(|_| UsageError::InvalidArgument {
+            position,
+            found: arg.clone(),
+        }),
+        None => Ok(default),
     }
 }
 
-fn run_robot(
-    instructions: Vec<i64>,
+fn parse_color_arg(args: &[String], position: usize, default: Color) -> Result<Color, UsageError> {
+    match args.get(position).map(String::as_str) {
+        Some("0") | Some("black") => Ok(Color::Black),
+        Some("1") | Some("white") => Ok(Color::White),
+        Some(other) => Err(UsageError::UnknownColor(other.to_string())),
+        None => Ok(default),
+    }
+}
+
+struct RobotRun {
+    panels: SparseGrid<Panel>,
+    bounds: Bounds,
+    stats: HullStats,
     initial_color: Color,
-) -> (HashMap<(i64, i64), Panel>, (i64, i64, i64, i64)) {
-    let mut computer = Computer::new(instructions);
-    let mut robot = Robot {
-        dir: Direction::Up,
-        pos: (0, 0),
-    };
-    let mut panels: HashMap<(i64, i64), Panel> = HashMap::new();
-    panels.insert((0, 0), Panel::new(initial_color));
-    let mut min_x = 10000000;
-    let mut min_y = 10000000;
-    let mut max_x = -10000000;
-    let mut max_y = -10000000;
+    steps: Vec<RobotStep>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct RobotStep {
+    pos: Pos,
+    dir: Direction,
+    color: Color,
+    turn: Turn,
+}
+
+trait Controller {
+    fn next(&mut self, observed: Color) -> Option<(Color, Turn)>;
+}
+
+struct IntcodeController {
+    computer: Computer,
+}
+
+impl IntcodeController {
+    pub fn new(instructions: Instructions) -> Self {
+        IntcodeController {
+            computer: Computer::new(instructions),
+        }
+    }
+
+    pub fn with_journal(self) -> Self {
+        IntcodeController {
+            computer: self.computer.with_journal(),
+        }
+    }
+}
+
+impl Controller for IntcodeController {
+    fn next(&mut self, observed: Color) -> Option<(Color, Turn)> {
+        self.computer.add_input(match observed {
+            Color::Black => 0,
+            Color::White => 1,
+        });
+        let (out1, out2) = run_twice(&mut self.computer)?;
+        let color = match out1 {
+            0 => Color::Black,
+            1 => Color::White,
+            _ => panic!("Unexpected out1 {}", out1),
+        };
+        let turn = match out2 {
+            0 => Turn::Left,
+            1 => Turn::Right,
+            _ => panic!("Unexpected out2 {}", out2),
+        };
+        Some((color, turn))
+    }
+}
+
+struct RuleTableController {
+    on_black: (Color, Turn),
+    on_white: (Color, Turn),
+    remaining_steps: usize,
+}
+
+impl RuleTableController {
+    pub fn new(on_black: (Color, Turn), on_white: (Color, Turn), steps: usize) -> Self {
+        RuleTableController {
+            on_black,
+            on_white,
+            remaining_steps: steps,
+        }
+    }
+
+    pub fn langtons_ant(steps: usize) -> Self {
+        RuleTableController::new(
+            (Color::White, Turn::Right),
+            (Color::Black, Turn::Left),
+            steps,
+        )
+    }
+}
+
+impl Controller for RuleTableController {
+    fn next(&mut self, observed: Color) -> Option<(Color, Turn)> {
+        if self.remaining_steps == 0 {
+            return None;
+        }
+        self.remaining_steps -= 1;
+        Some(match observed {
+            Color::Black => self.on_black,
+            Color::White => self.on_white,
+        })
+    }
+}
+
+struct ReplayController {
+    log: VecDeque<(Color, Turn)>,
+}
+
+impl ReplayController {
+    pub fn new(log: Vec<(Color, Turn)>) -> Self {
+        ReplayController { log: log.into() }
+    }
+
+    pub fn from_steps(steps: &[RobotStep]) -> Self {
+        ReplayController::new(steps.iter().map(|step| (step.color, step.turn)).collect())
+    }
+}
+
+impl Controller for ReplayController {
+    fn next(&mut self, _: Color) -> Option<(Color, Turn)> {
+        self.log.pop_front()
+    }
+}
+
+fn run_robot(instructions: Vec<i64>, initial_color: Color) -> RobotRun {
+    run_controller(&mut IntcodeController::new(instructions), initial_color)
+}
+
+fn run_controller(controller: &mut impl Controller, initial_color: Color) -> RobotRun {
+    let mut robot = Robot::new((0, 0), Direction::Up);
+    let mut panels = SparseGrid::new();
+    panels.set((0, 0), Panel::new(initial_color));
+    let mut steps = vec![];
     loop {
-        let input = if !panels.contains_key(&robot.pos) {
-            0
-        } else {
-            match panels[&robot.pos].color {
-                Color::Black => 0,
-                Color::White => 1,
-            }
+        let observed = match panels.get(robot.pos) {
+            Some(panel) => panel.color,
+            None => Color::Black,
         };
-        computer.add_input(input);
-        if let Some((out1, out2)) = run_twice(&mut computer) {
-            if !panels.contains_key(&robot.pos) {
-                panels.insert(robot.pos, Panel::new(Color::Black));
-            }
-            let panel = panels.get_mut(&robot.pos).unwrap();
-            panel.color = match out1 {
-                0 => Color::Black,
-                1 => Color::White,
-                _ => panic!("Unexpected out1 {}", out1),
-            };
-            match out2 {
-                0 => robot.turn_left(),
-                1 => robot.turn_right(),
-                _ => panic!("Unexpected out2 {}", out2),
+        if let Some((color, turn)) = controller.next(observed) {
+            let panel = panels.get_or_insert_with(robot.pos, || Panel::new(Color::Black));
+            panel.paint(steps.len(), color);
+            steps.push(RobotStep {
+                pos: robot.pos,
+                dir: robot.dir,
+                color,
+                turn,
+            });
+            match turn {
+                Turn::Left => robot.turn_left(),
+                Turn::Right => robot.turn_right(),
             }
         } else {
             break;
         }
         robot.advance();
-        if robot.pos.0 > max_x {
-            max_x = robot.pos.0;
-        }
-        if robot.pos.1 > max_y {
-            max_y = robot.pos.1;
+    }
+    let bounds = panels.bounds().unwrap().include(robot.pos);
+    let stats = HullStats::new(&panels, steps.len());
+    RobotRun {
+        panels,
+        bounds,
+        stats,
+        initial_color,
+        steps,
+    }
+}
+
+struct FleetRobot {
+    controller: Box<dyn Controller>,
+    robot: Robot,
+    initial_color: Color,
+    halted: bool,
+}
+
+impl FleetRobot {
+    pub fn new(
+        controller: impl Controller + 'static,
+        pos: Pos,
+        dir: Direction,
+        initial_color: Color,
+    ) -> Self {
+        FleetRobot {
+            controller: Box::new(controller),
+            robot: Robot::new(pos, dir),
+            initial_color,
+            halted: false,
         }
-        if robot.pos.0 < min_x {
-            min_x = robot.pos.0;
+    }
+
+    pub fn intcode(
+        instructions: Instructions,
+        pos: Pos,
+        dir: Direction,
+        initial_color: Color,
+    ) -> Self {
+        FleetRobot::new(
+            IntcodeController::new(instructions),
+            pos,
+            dir,
+            initial_color,
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Conflict {
+    step: usize,
+    pos: Pos,
+    robots: Vec<usize>,
+}
+
+struct FleetRun {
+    panels: SparseGrid<Panel>,
+    bounds: Bounds,
+    stats: HullStats,
+    robots: Vec<Robot>,
+    steps: Vec<(usize, RobotStep)>,
+    conflicts: Vec<Conflict>,
+}
+
+fn run_fleet(mut fleet: Vec<FleetRobot>) -> FleetRun {
+    let mut panels = SparseGrid::new();
+    let mut conflicts = vec![];
+    for (index, member) in fleet.iter().enumerate() {
+        let pos = member.robot.pos;
+        let robots = occupants(&fleet, pos);
+        if robots[0] == index {
+            panels.set(pos, Panel::new(member.initial_color));
+            if robots.len() > 1 {
+                conflicts.push(Conflict {
+                    step: 0,
+                    pos,
+                    robots,
+                });
+            }
         }
-        if robot.pos.1 < min_y {
-            min_y = robot.pos.1;
+    }
+    let mut steps = vec![];
+    while fleet.iter().any(|member| !member.halted) {
+        for index in 0..fleet.len() {
+            let member = &mut fleet[index];
+            if member.halted {
+                continue;
+            }
+            let robot = &mut member.robot;
+            let observed = match panels.get(robot.pos) {
+                Some(panel) => panel.color,
+                None => Color::Black,
+            };
+            if let Some((color, turn)) = member.controller.next(observed) {
+                let panel = panels.get_or_insert_with(robot.pos, || Panel::new(Color::Black));
+                panel.paint(steps.len(), color);
+                steps.push((
+                    index,
+                    RobotStep {
+                        pos: robot.pos,
+                        dir: robot.dir,
+                        color,
+                        turn,
+                    },
+                ));
+                match turn {
+                    Turn::Left => robot.turn_left(),
+                    Turn::Right => robot.turn_right(),
+                }
+            } else {
+                member.halted = true;
+                continue;
+            }
+            robot.advance();
+            let pos = robot.pos;
+            let robots = occupants(&fleet, pos);
+            if robots.len() > 1 {
+                conflicts.push(Conflict {
+                    step: steps.len(),
+                    pos,
+                    robots,
+                });
+            }
         }
     }
-    (panels, (min_x, min_y, max_x, max_y))
+    let robots: Vec<Robot> = fleet.iter().map(|member| member.robot).collect();
+    let bounds = robots
+        .iter()
+        .fold(panels.bounds().unwrap(), |bounds, robot| {
+            bounds.include(robot.pos)
+        });
+    let stats = HullStats::new(&panels, steps.len());
+    FleetRun {
+        panels,
+        bounds,
+        stats,
+        robots,
+        steps,
+        conflicts,
+    }
+}
+
+fn occupants(fleet: &[FleetRobot], pos: Pos) -> Vec<usize> {
+    fleet
+        .iter()
+        .enumerate()
+        .filter(|(_, member)| member.robot.pos == pos)
+        .map(|(index, _)| index)
+        .collect()
 }
 
 fn run_twice(computer: &mut Computer) -> Option<(i64, i64)> {
@@ -93,6 +497,189 @@ fn run_twice(computer: &mut Computer) -> Option<(i64, i64)> {
     None
 }
 
+struct Frame<'a> {
+    index: usize,
+    panels: &'a SparseGrid<Color>,
+    robot: &'a Robot,
+    bounds: Bounds,
+}
+
+impl<'a> Frame<'a> {
+    fn color(&self, pos: Pos) -> Color {
+        self.panels.get(pos).copied().unwrap_or(Color::Black)
+    }
+
+    pub fn to_ansi(&self) -> String {
+        let mut text = format!("step {}\n", self.index);
+        for y in self.bounds.min_y..=self.bounds.max_y {
+            for x in self.bounds.min_x..=self.bounds.max_x {
+                let background = match self.color((x, y)) {
+                    Color::Black => "40",
+                    Color::White => "47",
+                };
+                if self.robot.pos == (x, y) {
+                    text += &format!("\x1b[31;{}m{}\x1b[0m", background, self.robot.dir.arrow());
+                } else {
+                    text += &format!("\x1b[{}m \x1b[0m", background);
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn to_ppm(&self, scale: usize) -> Vec<u8> {
+        let width = self.bounds.width() as usize * scale;
+        let height = self.bounds.height() as usize * scale;
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for py in 0..height {
+            for px in 0..width {
+                let pos = (
+                    self.bounds.min_x + (px / scale) as i64,
+                    self.bounds.min_y + (py / scale) as i64,
+                );
+                let rgb = if self.robot.pos == pos {
+                    [255, 0, 0]
+                } else {
+                    match self.color(pos) {
+                        Color::Black => [0, 0, 0],
+                        Color::White => [255, 255, 255],
+                    }
+                };
+                ppm.extend_from_slice(&rgb);
+            }
+        }
+        ppm
+    }
+}
+
+impl RobotRun {
+    pub fn image(&self) -> Image {
+        Image::new(
+            self.bounds.width() as usize,
+            self.bounds.height() as usize,
+            self.bounds
+                .positions()
+                .map(|pos| match self.panels.get(pos).map(|panel| panel.color) {
+                    Some(Color::White) => 1,
+                    _ => 0,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn heatmap(&self) -> String {
+        self.panels.render_region(self.bounds, |panel| {
+            match panel.map(Panel::times_painted).unwrap_or(0) {
+                0 => ' ',
+                times @ 1..=9 => std::char::from_digit(times as u32, 10).unwrap(),
+                _ => '+',
+            }
+        })
+    }
+
+    pub fn panel_history(&self, pos: Pos) -> Option<String> {
+        let panel = self.panels.get(pos)?;
+        let colors: String = panel
+            .colors()
+            .into_iter()
+            .map(|color| match color {
+                Color::Black => 'B',
+                Color::White => 'W',
+            })
+            .collect();
+        Some(format!(
+            "{:?}: painted {} times, first at step {}, last at step {}, colors {}",
+            pos,
+            panel.times_painted(),
+            panel.first_step()?,
+            panel.last_step()?,
+            colors
+        ))
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = self.stats.to_string();
+        if let Some(history) = self
+            .stats
+            .most_painted_panel
+            .and_then(|(pos, _)| self.panel_history(pos))
+        {
+            report += &format!("{}\n", history);
+        }
+        report + &format!("visit heatmap:\n{}", self.heatmap())
+    }
+
+    pub fn for_each_frame(&self, every: usize, mut on_frame: impl FnMut(&Frame)) {
+        let every = every.max(1);
+        let bounds = self.bounds;
+        let mut panels = SparseGrid::new();
+        panels.set((0, 0), self.initial_color);
+        let mut robot = Robot::new((0, 0), Direction::Up);
+        for (index, step) in self.steps.iter().enumerate() {
+            if index % every == 0 {
+                on_frame(&Frame {
+                    index,
+                    panels: &panels,
+                    robot: &robot,
+                    bounds,
+                });
+            }
+            panels.set(step.pos, step.color);
+            match step.turn {
+                Turn::Left => robot.turn_left(),
+                Turn::Right => robot.turn_right(),
+            }
+            robot.advance();
+        }
+        on_frame(&Frame {
+            index: self.steps.len(),
+            panels: &panels,
+            robot: &robot,
+            bounds,
+        });
+    }
+
+    pub fn play_ansi(
+        &self,
+        out: &mut impl Write,
+        delay: std::time::Duration,
+    ) -> std::io::Result<()> {
+        let mut result = Ok(());
+        self.for_each_frame(1, |frame| {
+            if result.is_ok() {
+                result = write!(out, "\x1b[H\x1b[2J{}", frame.to_ansi()).and_then(|_| out.flush());
+                std::thread::sleep(delay);
+            }
+        });
+        result
+    }
+
+    pub fn export_ppm_frames(
+        &self,
+        dir: &str,
+        every: usize,
+        scale: usize,
+    ) -> std::io::Result<usize> {
+        std::fs::create_dir_all(dir)?;
+        let mut result = Ok(0);
+        self.for_each_frame(every, |frame| {
+            if let Ok(written) = result {
+                let path = format!("{}/frame_{:05}.ppm", dir, frame.index);
+                result = std::fs::write(path, frame.to_ppm(scale)).map(|_| written + 1);
+            }
+        });
+        result
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Turn {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 enum Direction {
     Up,
     Down,
@@ -100,12 +687,28 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    fn arrow(self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 struct Robot {
     dir: Direction,
-    pos: (i64, i64),
+    pos: Pos,
 }
 
 impl Robot {
+    pub fn new(pos: Pos, dir: Direction) -> Self {
+        Robot { dir, pos }
+    }
+
     fn turn_left(&mut self) {
         self.dir = match self.dir {
             Direction::Up => Direction::Left,
@@ -134,6 +737,7 @@ impl Robot {
     }
 }
 
+#[derive(Debug, PartialEq, Copy, Clone)]
 enum Color {
     Black,
     White,
@@ -141,12 +745,174 @@ enum Color {
 
 struct Panel {
     color: Color,
+    history: Vec<(usize, Color)>,
 }
 
 impl Panel {
     pub fn new(color: Color) -> Self {
-        Panel { color }
+        Panel {
+            color,
+            history: vec![],
+        }
     }
+
+    pub fn paint(&mut self, step: usize, color: Color) {
+        self.color = color;
+        self.history.push((step, color));
+    }
+
+    pub fn times_painted(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn first_step(&self) -> Option<usize> {
+        self.history.first().map(|(step, _)| *step)
+    }
+
+    pub fn last_step(&self) -> Option<usize> {
+        self.history.last().map(|(step, _)| *step)
+    }
+
+    pub fn colors(&self) -> Vec<Color> {
+        self.history.iter().map(|(_, color)| *color).collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct HullStats {
+    steps: usize,
+    distinct_panels: usize,
+    panels_painted_more_than_once: usize,
+    most_painted_panel: Option<(Pos, usize)>,
+    final_white_panels: usize,
+}
+
+impl HullStats {
+    pub fn new(panels: &SparseGrid<Panel>, steps: usize) -> Self {
+        let painted: Vec<(Pos, &Panel)> = panels
+            .cells()
+            .filter(|(_, panel)| panel.times_painted() > 0)
+            .collect();
+        HullStats {
+            steps,
+            distinct_panels: painted.len(),
+            panels_painted_more_than_once: painted
+                .iter()
+                .filter(|(_, panel)| panel.times_painted() > 1)
+                .count(),
+            most_painted_panel: painted
+                .iter()
+                .map(|(pos, panel)| (*pos, panel.times_painted()))
+                .max_by_key(|(pos, times)| (*times, std::cmp::Reverse(*pos))),
+            final_white_panels: panels
+                .cells()
+                .filter(|(_, panel)| panel.color == Color::White)
+                .count(),
+        }
+    }
+}
+
+impl std::fmt::Display for HullStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "steps: {}", self.steps)?;
+        writeln!(f, "distinct panels painted: {}", self.distinct_panels)?;
+        writeln!(
+            f,
+            "panels painted more than once: {}",
+            self.panels_painted_more_than_once
+        )?;
+        if let Some((pos, times)) = self.most_painted_panel {
+            writeln!(f, "most painted panel: {:?} ({} times)", pos, times)?;
+        }
+        writeln!(f, "final white panels: {}", self.final_white_panels)
+    }
+}
+
+fn ocr_panels(panels: &SparseGrid<Panel>) -> Option<String> {
+    let mut white = SparseGrid::new();
+    for (pos, panel) in panels.cells() {
+        if panel.color == Color::White {
+            white.set(pos, true);
+        }
+    }
+    let bounds = white.bounds()?;
+    let pixels: Vec<Vec<bool>> = (bounds.min_y..=bounds.max_y)
+        .map(|y| {
+            (bounds.min_x..=bounds.max_x)
+                .map(|x| white.contains((x, y)))
+                .collect()
+        })
+        .collect();
+    ocr(&pixels)
+}
+
+const FONT: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+const GLYPH_HEIGHT: usize = 6;
+const GLYPH_PITCH: usize = 5;
+
+fn ocr(pixels: &[Vec<bool>]) -> Option<String> {
+    let first = pixels.iter().position(|row| row.contains(&true))?;
+    let last = pixels.iter().rposition(|row| row.contains(&true))?;
+    let rows = &pixels[first..=last];
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let column = |x: usize| -> Vec<bool> {
+        rows.iter()
+            .map(|row| row.get(x).copied().unwrap_or(false))
+            .collect()
+    };
+    let read = |start: usize| -> Option<String> {
+        let mut text = String::new();
+        for x in (start..width).step_by(GLYPH_PITCH) {
+            let glyph: Vec<Vec<bool>> = (x..x + GLYPH_PITCH).map(column).collect();
+            if glyph.iter().any(|column| column.contains(&true)) {
+                text.push(recognize(&glyph)?);
+            }
+        }
+        Some(text)
+    };
+    let left = (0..width).find(|x| column(*x).contains(&true))?;
+    (0..GLYPH_PITCH)
+        .filter_map(|shift| left.checked_sub(shift))
+        .find_map(read)
+}
+
+fn recognize(columns: &[Vec<bool>]) -> Option<char> {
+    FONT.iter()
+        .find(|(_, rows)| glyph_columns(rows) == columns)
+        .map(|(letter, _)| *letter)
+}
+
+fn glyph_columns(rows: &[&str; 6]) -> Vec<Vec<bool>> {
+    (0..GLYPH_PITCH)
+        .map(|x| {
+            rows.iter()
+                .map(|row| row.as_bytes().get(x) == Some(&b'#'))
+                .collect::<Vec<bool>>()
+        })
+        .collect()
 }
 
 type Instructions = Vec<i64>;
@@ -157,6 +923,20 @@ struct Computer {
     memory: HashMap<i64, i64>,
     ptr: i64,
     relative_base: i64,
+    journal: Option<Vec<Vec<Undo>>>,
+}
+
+enum Undo {
+    Ptr(i64),
+    RelativeBase(i64),
+    Write(i64, Option<i64>),
+    Input(i64),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum StepBackError {
+    NoJournal,
+    JournalExhausted { undone: usize },
 }
 
 enum ComputerExecution {
@@ -172,9 +952,15 @@ impl Computer {
             input: VecDeque::new(),
             ptr: 0,
             relative_base: 0,
+            journal: None,
         }
     }
 
+    pub fn with_journal(mut self) -> Self {
+        self.journal = Some(vec![]);
+        self
+    }
+
     pub fn with_input(mut self, input: i64) -> Self {
         self.add_input(input);
         self
@@ -195,30 +981,34 @@ impl Computer {
     pub fn next_output(&mut self) -> ComputerExecution {
         loop {
             let op = self.opcode();
+            if op.de != 99 {
+                self.begin_step();
+            }
             match op.de {
                 1 => {
                     let first = self.get_first(op);
                     let second = self.get_second(op);
                     self.set_third(op, first + second);
-                    self.ptr += 4;
+                    self.jump(self.ptr + 4);
                 }
                 2 => {
                     let first = self.get_first(op);
                     let second = self.get_second(op);
                     self.set_third(op, first * second);
-                    self.ptr += 4;
+                    self.jump(self.ptr + 4);
                 }
                 3 => {
                     if let Some(input) = self.input.pop_front() {
+                        self.record(Undo::Input(input));
                         self.set_first(op, input);
                     } else {
                         panic!("Missing input!");
                     }
-                    self.ptr += 2;
+                    self.jump(self.ptr + 2);
                 }
                 4 => {
                     let first = self.get_first(op);
-                    self.ptr += 2;
+                    self.jump(self.ptr + 2);
                     return ComputerExecution::Yield(first);
                 }
                 5 => {
@@ -228,9 +1018,9 @@ impl Computer {
                         if second < 0 {
                             panic!("Second can't be 0 here: {}", second);
                         }
-                        self.ptr = second;
+                        self.jump(second);
                     } else {
-                        self.ptr += 3;
+                        self.jump(self.ptr + 3);
                     }
                 }
                 6 => {
@@ -240,27 +1030,28 @@ impl Computer {
                         if second < 0 {
                             panic!("Second can't be less than 0 here: {}", second);
                         }
-                        self.ptr = second;
+                        self.jump(second);
                     } else {
-                        self.ptr += 3;
+                        self.jump(self.ptr + 3);
                     }
                 }
                 7 => {
                     let first = self.get_first(op);
                     let second = self.get_second(op);
                     self.set_third(op, if first < second { 1 } else { 0 });
-                    self.ptr += 4;
+                    self.jump(self.ptr + 4);
                 }
                 8 => {
                     let first = self.get_first(op);
                     let second = self.get_second(op);
                     self.set_third(op, if first == second { 1 } else { 0 });
-                    self.ptr += 4;
+                    self.jump(self.ptr + 4);
                 }
                 9 => {
                     let first = self.get_first(op);
+                    self.record(Undo::RelativeBase(self.relative_base));
                     self.relative_base += first;
-                    self.ptr += 2;
+                    self.jump(self.ptr + 2);
                 }
                 99 => return ComputerExecution::Halt,
                 _ => panic!("Opcode not implemented: {}", op.de),
@@ -275,27 +1066,95 @@ impl Computer {
         *self.access(op.b, 2)
     }
     pub fn set_third(&mut self, op: Opcode, value: i64) {
-        *self.access(op.a, 3) = value;
+        self.write(op.a, 3, value);
     }
     pub fn set_first(&mut self, op: Opcode, value: i64) {
-        *self.access(op.c, 1) = value;
+        self.write(op.c, 1, value);
     }
 
     pub fn access(&mut self, mode: u16, offset: i64) -> &mut i64 {
+        let address = self.address(mode, offset);
+        self.instruction(address)
+    }
+
+    fn address(&mut self, mode: u16, offset: i64) -> i64 {
         match mode {
-            0 => {
-                let ptr = *self.instruction(self.ptr + offset);
-                self.instruction(ptr)
-            }
-            1 => self.instruction(self.ptr + offset),
-            2 => {
-                let ptr = *self.instruction(self.ptr + offset);
-                self.instruction(ptr + self.relative_base)
-            }
+            0 => *self.instruction(self.ptr + offset),
+            1 => self.ptr + offset,
+            2 => *self.instruction(self.ptr + offset) + self.relative_base,
             _ => panic!("Mode not implemented: {}", mode),
         }
     }
 
+    fn write(&mut self, mode: u16, offset: i64, value: i64) {
+        let address = self.address(mode, offset);
+        if self.journal.is_some() {
+            let previous = if address < self.instructions.len() as i64 {
+                Some(self.instructions[address as usize])
+            } else {
+                self.memory.get(&address).copied()
+            };
+            self.record(Undo::Write(address, previous));
+        }
+        *self.instruction(address) = value;
+    }
+
+    fn jump(&mut self, ptr: i64) {
+        self.record(Undo::Ptr(self.ptr));
+        self.ptr = ptr;
+    }
+
+    fn begin_step(&mut self) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push(vec![]);
+        }
+    }
+
+    fn record(&mut self, undo: Undo) {
+        if let Some(step) = self.journal.as_mut().and_then(|journal| journal.last_mut()) {
+            step.push(undo);
+        }
+    }
+
+    pub fn journal_len(&self) -> usize {
+        self.journal.as_ref().map_or(0, Vec::len)
+    }
+
+    pub fn step_back(&mut self, n: usize) -> Result<(), StepBackError> {
+        if self.journal.is_none() {
+            return Err(StepBackError::NoJournal);
+        }
+        for undone in 0..n {
+            let step = match self.journal.as_mut().and_then(Vec::pop) {
+                Some(step) => step,
+                None => return Err(StepBackError::JournalExhausted { undone }),
+            };
+            for undo in step.into_iter().rev() {
+                match undo {
+                    Undo::Ptr(ptr) => self.ptr = ptr,
+                    Undo::RelativeBase(relative_base) => self.relative_base = relative_base,
+                    Undo::Write(address, Some(previous)) => *self.instruction(address) = previous,
+                    Undo::Write(address, None) => {
+                        self.memory.remove(&address);
+                    }
+                    Undo::Input(input) => self.input.push_front(input),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn step_back_until_written(&mut self, address: i64) -> Option<usize> {
+        let journal = self.journal.as_ref()?;
+        let last_write = journal.iter().rposition(|step| {
+            step.iter()
+                .any(|undo| matches!(undo, Undo::Write(written, _) if *written == address))
+        })?;
+        let steps = journal.len() - last_write;
+        self.step_back(steps).ok()?;
+        Some(steps)
+    }
+
     pub fn instruction(&mut self, position: i64) -> &mut i64 {
         if position >= self.instructions.len() as i64 {
             if !self.memory.contains_key(&position) {
@@ -331,9 +1190,533 @@ fn to_opcode(mut n: i64) -> Opcode {
     Opcode { a, b, c, de }
 }
 
+mod grid {
+    use std::collections::HashMap;
+    use std::fmt::Display;
+
+    pub type Pos = (i64, i64);
+
+    #[allow(dead_code)]
+    const NEIGHBORS: [Pos; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    #[allow(dead_code)]
+    const DIAGONAL_NEIGHBORS: [Pos; 8] = [
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+    ];
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub struct Bounds {
+        pub min_x: i64,
+        pub min_y: i64,
+        pub max_x: i64,
+        pub max_y: i64,
+    }
+
+    impl Bounds {
+        pub fn new(pos: Pos) -> Self {
+            Bounds {
+                min_x: pos.0,
+                min_y: pos.1,
+                max_x: pos.0,
+                max_y: pos.1,
+            }
+        }
+
+        pub fn include(self, pos: Pos) -> Self {
+            Bounds {
+                min_x: self.min_x.min(pos.0),
+                min_y: self.min_y.min(pos.1),
+                max_x: self.max_x.max(pos.0),
+                max_y: self.max_y.max(pos.1),
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn expand(self, margin: i64) -> Self {
+            Bounds {
+                min_x: self.min_x - margin,
+                min_y: self.min_y - margin,
+                max_x: self.max_x + margin,
+                max_y: self.max_y + margin,
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn contains(self, pos: Pos) -> bool {
+            pos.0 >= self.min_x && pos.0 <= self.max_x && pos.1 >= self.min_y && pos.1 <= self.max_y
+        }
+
+        pub fn width(self) -> i64 {
+            self.max_x - self.min_x + 1
+        }
+
+        pub fn height(self) -> i64 {
+            self.max_y - self.min_y + 1
+        }
+
+        pub fn positions(self) -> impl Iterator<Item = Pos> {
+            (self.min_y..=self.max_y)
+                .flat_map(move |y| (self.min_x..=self.max_x).map(move |x| (x, y)))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    #[allow(dead_code)]
+    pub struct RaggedRow {
+        pub row: usize,
+        pub expected: usize,
+        pub found: usize,
+    }
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    #[allow(dead_code)]
+    pub struct OutOfBounds(pub Pos);
+
+    pub trait Grid<T> {
+        fn get(&self, pos: Pos) -> Option<&T>;
+        #[allow(dead_code)]
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T>;
+        fn bounds(&self) -> Option<Bounds>;
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a>;
+
+        #[allow(dead_code)]
+        fn neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn diagonal_neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &DIAGONAL_NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn around(&self, pos: Pos, offsets: &[Pos]) -> Vec<(Pos, &T)> {
+            offsets
+                .iter()
+                .map(|(dx, dy)| (pos.0 + dx, pos.1 + dy))
+                .filter_map(|neighbor| self.get(neighbor).map(|cell| (neighbor, cell)))
+                .collect()
+        }
+
+        #[allow(dead_code)]
+        fn render<D: Display>(&self, cell: impl Fn(Option<&T>) -> D) -> String {
+            match self.bounds() {
+                Some(bounds) => self.render_region(bounds, cell),
+                None => String::new(),
+            }
+        }
+
+        fn render_region<D: Display>(
+            &self,
+            bounds: Bounds,
+            cell: impl Fn(Option<&T>) -> D,
+        ) -> String {
+            let mut text = String::new();
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    text += &cell(self.get((x, y))).to_string();
+                }
+                text.push('\n');
+            }
+            text
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct SparseGrid<T> {
+        cells: HashMap<Pos, T>,
+        bounds: Option<Bounds>,
+    }
+
+    impl<T> SparseGrid<T> {
+        pub fn new() -> Self {
+            SparseGrid {
+                cells: HashMap::new(),
+                bounds: None,
+            }
+        }
+
+        pub fn len(&self) -> usize {
+            self.cells.len()
+        }
+
+        #[allow(dead_code)]
+        pub fn is_empty(&self) -> bool {
+            self.cells.is_empty()
+        }
+
+        pub fn contains(&self, pos: Pos) -> bool {
+            self.cells.contains_key(&pos)
+        }
+
+        pub fn set(&mut self, pos: Pos, value: T) {
+            self.include(pos);
+            self.cells.insert(pos, value);
+        }
+
+        pub fn get_or_insert_with(&mut self, pos: Pos, value: impl FnOnce() -> T) -> &mut T {
+            self.include(pos);
+            self.cells.entry(pos).or_insert_with(value)
+        }
+
+        fn include(&mut self, pos: Pos) {
+            self.bounds = Some(match self.bounds {
+                Some(bounds) => bounds.include(pos),
+                None => Bounds::new(pos),
+            });
+        }
+    }
+
+    impl<T> Default for SparseGrid<T> {
+        fn default() -> Self {
+            SparseGrid::new()
+        }
+    }
+
+    impl<T> Grid<T> for SparseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.cells.get(&pos)
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            self.cells.get_mut(&pos)
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            self.bounds
+        }
+
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            Box::new(self.cells.iter().map(|(pos, cell)| (*pos, cell)))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    #[allow(dead_code)]
+    pub struct DenseGrid<T> {
+        width: usize,
+        height: usize,
+        cells: Vec<T>,
+    }
+
+    #[allow(dead_code)]
+    impl<T> DenseGrid<T> {
+        pub fn new(width: usize, height: usize, fill: T) -> Self
+        where
+            T: Clone,
+        {
+            DenseGrid {
+                width,
+                height,
+                cells: vec![fill; width * height],
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, RaggedRow> {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if let Some((row, found)) = rows
+                .iter()
+                .map(Vec::len)
+                .enumerate()
+                .find(|(_, len)| *len != width)
+            {
+                return Err(RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+            Ok(DenseGrid {
+                width,
+                height,
+                cells: rows.into_iter().flatten().collect(),
+            })
+        }
+
+        pub fn map<U>(&self, cell: impl Fn(Pos, &T) -> U) -> DenseGrid<U> {
+            DenseGrid {
+                width: self.width,
+                height: self.height,
+                cells: self.cells().map(|(pos, value)| cell(pos, value)).collect(),
+            }
+        }
+
+        pub fn set(&mut self, pos: Pos, value: T) -> Result<(), OutOfBounds> {
+            let index = self.index(pos).ok_or(OutOfBounds(pos))?;
+            self.cells[index] = value;
+            Ok(())
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        fn index(&self, pos: Pos) -> Option<usize> {
+            if pos.0 < 0 || pos.1 < 0 || pos.0 >= self.width as i64 || pos.1 >= self.height as i64 {
+                None
+            } else {
+                Some(pos.0 as usize + pos.1 as usize * self.width)
+            }
+        }
+    }
+
+    impl<T> Grid<T> for DenseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.index(pos).map(|index| &self.cells[index])
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            match self.index(pos) {
+                Some(index) => Some(&mut self.cells[index]),
+                None => None,
+            }
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            if self.cells.is_empty() {
+                None
+            } else {
+                Some(Bounds {
+                    min_x: 0,
+                    min_y: 0,
+                    max_x: self.width as i64 - 1,
+                    max_y: self.height as i64 - 1,
+                })
+            }
+        }
+
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            let width = self.width;
+            Box::new(
+                self.cells.iter().enumerate().map(move |(index, cell)| {
+                    (((index % width) as i64, (index / width) as i64), cell)
+                }),
+            )
+        }
+    }
+}
+
+#[allow(dead_code)]
+mod image {
+    use std::io;
+
+    pub type Rgb = [u8; 3];
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Palette {
+        colors: Vec<Rgb>,
+    }
+
+    impl Palette {
+        pub fn new(colors: Vec<Rgb>) -> Self {
+            if colors.is_empty() {
+                panic!("A palette needs at least one color.");
+            }
+            Palette { colors }
+        }
+
+        pub fn monochrome() -> Self {
+            Palette::new(vec![[0, 0, 0], [255, 255, 255]])
+        }
+
+        pub fn len(&self) -> usize {
+            self.colors.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.colors.is_empty()
+        }
+
+        pub fn color(&self, index: usize) -> Rgb {
+            *self
+                .colors
+                .get(index)
+                .unwrap_or_else(|| panic!("No color {} in a palette of {}", index, self.len()))
+        }
+
+        pub fn is_dark(&self, index: usize) -> bool {
+            let [r, g, b] = self.color(index);
+            299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) < 128_000
+        }
+
+        pub fn hex(&self, index: usize) -> String {
+            let [r, g, b] = self.color(index);
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<usize>,
+        scale: usize,
+        palette: Palette,
+    }
+
+    impl Image {
+        pub fn new(width: usize, height: usize, pixels: Vec<usize>) -> Self {
+            if pixels.len() != width * height {
+                panic!(
+                    "{} pixels can't form a {}x{} image.",
+                    pixels.len(),
+                    width,
+                    height
+                );
+            }
+            Image {
+                width,
+                height,
+                pixels,
+                scale: 1,
+                palette: Palette::monochrome(),
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<usize>>) -> Self {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if rows.iter().any(|row| row.len() != width) {
+                panic!("Rows of different length can't form an image.");
+            }
+            Image::new(width, height, rows.into_iter().flatten().collect())
+        }
+
+        pub fn from_bools(rows: &[Vec<bool>]) -> Self {
+            Image::from_rows(
+                rows.iter()
+                    .map(|row| row.iter().map(|set| *set as usize).collect())
+                    .collect(),
+            )
+        }
+
+        pub fn with_scale(mut self, scale: usize) -> Self {
+            self.scale = scale.max(1);
+            self
+        }
+
+        pub fn with_palette(mut self, palette: Palette) -> Self {
+            self.palette = palette;
+            self
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> Option<usize> {
+            if x < self.width && y < self.height {
+                Some(self.pixels[x + y * self.width])
+            } else {
+                None
+            }
+        }
+
+        fn scaled_size(&self) -> (usize, usize) {
+            (self.width * self.scale, self.height * self.scale)
+        }
+
+        fn scaled(&self, px: usize, py: usize) -> usize {
+            self.pixels[px / self.scale + py / self.scale * self.width]
+        }
+
+        pub fn to_pbm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut pbm = format!("P4\n{} {}\n", width, height).into_bytes();
+            for py in 0..height {
+                let mut row = vec![0u8; width.div_ceil(8)];
+                for px in 0..width {
+                    if self.palette.is_dark(self.scaled(px, py)) {
+                        row[px / 8] |= 0x80 >> (px % 8);
+                    }
+                }
+                pbm.extend(row);
+            }
+            pbm
+        }
+
+        pub fn to_ppm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+            for py in 0..height {
+                for px in 0..width {
+                    ppm.extend_from_slice(&self.palette.color(self.scaled(px, py)));
+                }
+            }
+            ppm
+        }
+
+        pub fn to_svg(&self) -> String {
+            let (width, height) = self.scaled_size();
+            let mut svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">\n",
+                width, height
+            );
+            svg += &format!(
+                "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                width,
+                height,
+                self.palette.hex(0)
+            );
+            for y in 0..self.height {
+                let mut x = 0;
+                while x < self.width {
+                    let index = self.pixels[x + y * self.width];
+                    let run = (x..self.width)
+                        .take_while(|&end| self.pixels[end + y * self.width] == index)
+                        .count();
+                    if index != 0 {
+                        svg += &format!(
+                            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                            x * self.scale,
+                            y * self.scale,
+                            run * self.scale,
+                            self.scale,
+                            self.palette.hex(index)
+                        );
+                    }
+                    x += run;
+                }
+            }
+            svg + "</svg>\n"
+        }
+
+        pub fn save(&self, path: &str) -> io::Result<()> {
+            let bytes = match path.rsplit('.').next() {
+                Some("pbm") => self.to_pbm(),
+                Some("ppm") => self.to_ppm(),
+                Some("svg") => self.to_svg().into_bytes(),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown image format: {}", path),
+                    ))
+                }
+            };
+            std::fs::write(path, bytes)
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
+    use super::grid::{DenseGrid, OutOfBounds, RaggedRow};
     use super::*;
 
     macro_rules! eq_tests {
@@ -373,6 +1756,116 @@ mod test {
             .join(",")
     }
 
+    fn journaled(instructions: Instructions, inputs: &[i64]) -> Computer {
+        let mut computer = Computer::new(instructions).with_journal();
+        for input in inputs {
+            computer.add_input(*input);
+        }
+        computer.run();
+        computer
+    }
+
+    fn step_back(mut computer: Computer, n: usize) -> Computer {
+        let _ = computer.step_back(n);
+        computer
+    }
+
+    fn step_back_until_written(mut computer: Computer, address: i64) -> (Option<usize>, i64) {
+        let steps = computer.step_back_until_written(address);
+        (steps, computer.ptr)
+    }
+
+    fn scripted(outputs: &[(i64, i64)]) -> Instructions {
+        let mut program = vec![];
+        for (color, turn) in outputs {
+            program.extend_from_slice(&[3, 1000, 104, *color, 104, *turn]);
+        }
+        program.push(99);
+        program
+    }
+
+    const EXAMPLE_OUTPUTS: [(i64, i64); 7] =
+        [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+
+    fn example_run() -> RobotRun {
+        run_robot(scripted(&EXAMPLE_OUTPUTS), Color::Black)
+    }
+
+    const EXAMPLE_LOG: [(Color, Turn); 7] = [
+        (Color::White, Turn::Left),
+        (Color::Black, Turn::Left),
+        (Color::White, Turn::Left),
+        (Color::White, Turn::Left),
+        (Color::Black, Turn::Right),
+        (Color::White, Turn::Left),
+        (Color::White, Turn::Left),
+    ];
+
+    fn fleet_of_ants(starts: &[(Pos, Direction, usize)]) -> FleetRun {
+        run_fleet(
+            starts
+                .iter()
+                .map(|(pos, dir, steps)| {
+                    FleetRobot::new(
+                        RuleTableController::langtons_ant(*steps),
+                        *pos,
+                        *dir,
+                        Color::Black,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    type ScriptedRobot<'a> = (Pos, Direction, &'a [(i64, i64)]);
+
+    fn scripted_fleet(robots: &[ScriptedRobot]) -> FleetRun {
+        run_fleet(
+            robots
+                .iter()
+                .map(|(pos, dir, outputs)| {
+                    FleetRobot::intcode(scripted(outputs), *pos, *dir, Color::Black)
+                })
+                .collect(),
+        )
+    }
+
+    fn langtons_ant_drift(steps: usize) -> i64 {
+        let bounds =
+            run_controller(&mut RuleTableController::langtons_ant(steps), Color::Black).bounds;
+        bounds.width().max(bounds.height())
+    }
+
+    fn frame_indexes(run: &RobotRun, every: usize) -> Vec<usize> {
+        let mut indexes = vec![];
+        run.for_each_frame(every, |frame| indexes.push(frame.index));
+        indexes
+    }
+
+    fn last_frame_ansi(run: &RobotRun) -> String {
+        let mut text = String::new();
+        run.for_each_frame(1, |frame| text = frame.to_ansi());
+        text
+    }
+
+    fn first_frame_ppm(run: &RobotRun, scale: usize) -> Vec<u8> {
+        let mut ppm = vec![];
+        run.for_each_frame(1, |frame| {
+            if frame.index == 0 {
+                ppm = frame.to_ppm(scale)
+            }
+        });
+        ppm
+    }
+
+    fn sparse_grid(cells: &[(Pos, char)]) -> SparseGrid<char> {
+        let mut grid = SparseGrid::new();
+        for (pos, cell) in cells {
+            grid.set(*pos, *cell);
+        }
+        grid
+    }
+
     fn count_digits(mut n: i64) -> i64 {
         let mut counter = 0;
         while n > 0 {
@@ -382,6 +1875,10 @@ mod test {
         counter
     }
 
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
     eq_tests! {
         computer_one_1: stringify(computer_one(vec![1, 0, 0, 0, 99]).computer.instructions) => "2,0,0,0,99";
         computer_one_2: stringify(computer_one(vec![2, 3, 0, 3, 99]).computer.instructions) => "2,3,0,6,99";
@@ -411,6 +1908,93 @@ mod test {
         relative_mode_example_3: computer_n(vec![104,1125899906842624,99], 42).outputs[0] => 1125899906842624;
 
         op_203_base_1000_input_42_eq_42: computer_n(vec![109, 1000, 203, 5, 4, 1005, 99], 42).outputs[0] => 42;
+
+        example_panels: example_run().panels.len() => 6;
+        example_stats: example_run().stats => HullStats { steps: 7, distinct_panels: 6, panels_painted_more_than_once: 1, most_painted_panel: Some(((0, 0), 2)), final_white_panels: 4 };
+        example_panel_history: example_run().panels.get((0, 0)).map(|panel| (panel.times_painted(), panel.first_step(), panel.last_step(), panel.colors())) => Some((2, Some(0), Some(4), vec![Color::White, Color::Black]));
+        example_panel_history_unknown: example_run().panel_history((5, 5)) => None;
+        example_unpainted_panel: Panel::new(Color::White).first_step() => None;
+        example_heatmap: example_run().heatmap() => "  1\n121\n11 \n";
+        example_report: example_run().report() => "steps: 7\ndistinct panels painted: 6\npanels painted more than once: 1\nmost painted panel: (0, 0) (2 times)\nfinal white panels: 4\n(0, 0): painted 2 times, first at step 0, last at step 4, colors WB\nvisit heatmap:\n  1\n121\n11 \n";
+        part_1_stats_match_panels: { let run = run_robot(PUZZLE_INPUT.to_vec(), Color::Black); (run.stats.distinct_panels, run.stats.steps >= run.panels.len()) } => (2016, true);
+        controller_intcode_example: IntcodeController::new(scripted(&EXAMPLE_OUTPUTS)).next(Color::Black) => Some((Color::White, Turn::Left));
+        controller_replay_example: run_controller(&mut ReplayController::new(EXAMPLE_LOG.to_vec()), Color::Black).steps => example_run().steps;
+        controller_replay_ignores_observed: ReplayController::new(EXAMPLE_LOG.to_vec()).next(Color::White) => Some((Color::White, Turn::Left));
+        controller_replay_part_2: ocr_panels(&run_controller(&mut ReplayController::from_steps(&run_robot(PUZZLE_INPUT.to_vec(), Color::White).steps), Color::White).panels) => Some("RAPRCBPH".to_string());
+        controller_rule_table_stops: run_controller(&mut RuleTableController::langtons_ant(0), Color::Black).steps.len() => 0;
+        controller_langtons_ant_steps: run_controller(&mut RuleTableController::langtons_ant(11000), Color::Black).steps.len() => 11000;
+        controller_langtons_ant_square: run_controller(&mut RuleTableController::langtons_ant(4), Color::Black).panels.render(|panel| if panel.map(|panel| panel.color) == Some(Color::White) { '#' } else { '.' }) => "##\n##\n";
+        controller_langtons_ant_highway: langtons_ant_drift(11000) > langtons_ant_drift(9000) + 20 => true;
+        example_steps: example_run().steps.len() => 7;
+        example_first_step: example_run().steps[0] => RobotStep { pos: (0, 0), dir: Direction::Up, color: Color::White, turn: Turn::Left };
+        example_fifth_step: example_run().steps[4] => RobotStep { pos: (0, 0), dir: Direction::Up, color: Color::Black, turn: Turn::Right };
+        example_frames_every_1: frame_indexes(&example_run(), 1) => vec![0, 1, 2, 3, 4, 5, 6, 7];
+        example_frames_every_3: frame_indexes(&example_run(), 3) => vec![0, 3, 6, 7];
+        example_last_frame_ansi: last_frame_ansi(&example_run()).lines().map(|line| line.replace("\x1b[0m", "").replace("\x1b[40m ", ".").replace("\x1b[47m ", "#").replace("\x1b[31;40m", "")).collect::<Vec<_>>() => vec!["step 7", ".<#", "..#", "##."];
+        example_first_frame_ppm_header: first_frame_ppm(&example_run(), 2)[..11].to_vec() => b"P6\n6 6\n255\n".to_vec();
+        example_first_frame_ppm_size: first_frame_ppm(&example_run(), 2).len() => 11 + 6 * 6 * 3;
+
+        fleet_single_robot_steps: scripted_fleet(&[((0, 0), Direction::Up, &EXAMPLE_OUTPUTS)]).steps.into_iter().map(|(_, step)| step).collect::<Vec<_>>() => example_run().steps;
+        fleet_single_robot_stats: scripted_fleet(&[((0, 0), Direction::Up, &EXAMPLE_OUTPUTS)]).stats => example_run().stats;
+        fleet_single_robot_part_1: run_fleet(vec![FleetRobot::intcode(PUZZLE_INPUT.to_vec(), (0, 0), Direction::Up, Color::Black)]).stats.distinct_panels => 2016;
+        fleet_turn_order: scripted_fleet(&[((0, 0), Direction::Up, &[(1, 0), (1, 0)]), ((5, 0), Direction::Up, &[(1, 1)]), ((9, 0), Direction::Up, &[(1, 0), (1, 0)])]).steps.iter().map(|(robot, _)| *robot).collect::<Vec<_>>() => vec![0, 1, 2, 0, 2];
+        fleet_final_robots: scripted_fleet(&[((0, 0), Direction::Up, &[(1, 1)]), ((5, 5), Direction::Down, &[(1, 0)])]).robots => vec![Robot::new((1, 0), Direction::Right), Robot::new((6, 5), Direction::Right)];
+        fleet_collision: scripted_fleet(&[((0, 0), Direction::Up, &[(1, 1)]), ((2, 0), Direction::Up, &[(1, 0)])]).conflicts => vec![Conflict { step: 2, pos: (1, 0), robots: vec![0, 1] }];
+        fleet_starting_conflict: fleet_of_ants(&[((3, 3), Direction::Up, 0), ((0, 0), Direction::Up, 0), ((3, 3), Direction::Left, 0)]).conflicts => vec![Conflict { step: 0, pos: (3, 3), robots: vec![0, 2] }];
+        fleet_no_conflict: fleet_of_ants(&[((0, 0), Direction::Up, 100), ((50, 50), Direction::Up, 100)]).conflicts => vec![];
+        fleet_shared_panels: fleet_of_ants(&[((0, 0), Direction::Up, 1), ((0, 1), Direction::Left, 2)]).panels.get((0, 0)).map(|panel| panel.history.clone()) => Some(vec![(0, Color::White), (2, Color::Black)]);
+        fleet_bounds_include_robots: fleet_of_ants(&[((0, 0), Direction::Up, 1), ((0, 1), Direction::Left, 2)]).bounds => Bounds { min_x: -1, min_y: 0, max_x: 1, max_y: 1 };
+
+        example_image: example_run().image() => Image::from_rows(vec![vec![0, 0, 1], vec![0, 0, 1], vec![1, 1, 0]]);
+        example_image_pbm: example_run().image().to_pbm() => b"P4\n3 3\n\xc0\xc0\x20".to_vec();
+        example_image_svg_rects: example_run().image().with_scale(5).to_svg().matches("fill=\"#ffffff\"").count() => 3;
+        part_2_image_size: { let image = run_robot(PUZZLE_INPUT.to_vec(), Color::White).image(); (image.width(), image.height()) } => (43, 6);
+
+        ocr_panels_part_2: ocr_panels(&run_robot(PUZZLE_INPUT.to_vec(), Color::White).panels) => Some("RAPRCBPH".to_string());
+        ocr_panels_part_1_is_not_text: ocr_panels(&run_robot(PUZZLE_INPUT.to_vec(), Color::Black).panels) => None;
+        ocr_full_width_y: ocr(&["#...#####", "#...#...#", ".#.#...#.", "..#...#..", "..#..#...", "..#..####"].iter().map(|row| row.chars().map(|c| c == '#').collect()).collect::<Vec<_>>()) => Some("YZ".to_string());
+        ocr_panels_without_white: ocr_panels(&SparseGrid::new()) => None;
+        ocr_font_round_trip: FONT.iter().map(|(_, rows)| ocr(&rows.iter().map(|row| row.chars().map(|c| c == '#').collect()).collect::<Vec<_>>()).unwrap()).collect::<String>() => FONT.iter().map(|(letter, _)| *letter).collect::<String>();
+
+        grid_bounds_include: Bounds::new((0, 0)).include((3, -2)).include((-1, 1)) => Bounds { min_x: -1, min_y: -2, max_x: 3, max_y: 1 };
+        grid_bounds_size: (Bounds::new((0, 0)).include((3, -2)).width(), Bounds::new((0, 0)).include((3, -2)).height()) => (4, 3);
+        grid_bounds_positions: Bounds::new((0, 0)).include((1, 1)).positions().collect::<Vec<_>>() => vec![(0, 0), (1, 0), (0, 1), (1, 1)];
+        grid_sparse_bounds: sparse_grid(&[((2, -3), 'a'), ((-4, 5), 'b')]).bounds() => Some(Bounds { min_x: -4, min_y: -3, max_x: 2, max_y: 5 });
+        grid_sparse_empty_bounds: SparseGrid::<char>::new().bounds() => None;
+        grid_sparse_neighbors: sparse_grid(&[((0, 0), 'a'), ((1, 0), 'b'), ((1, 1), 'c'), ((0, -1), 'd')]).neighbors((0, 0)) => vec![((0, -1), &'d'), ((1, 0), &'b')];
+        grid_sparse_diagonal_neighbors: sparse_grid(&[((0, 0), 'a'), ((1, 0), 'b'), ((1, 1), 'c')]).diagonal_neighbors((0, 0)) => vec![((1, 0), &'b'), ((1, 1), &'c')];
+        grid_sparse_render: sparse_grid(&[((0, 0), 'a'), ((2, 1), 'b')]).render(|cell| *cell.unwrap_or(&'.')) => "a..\n..b\n";
+        grid_dense_from_rows: DenseGrid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap().get((2, 1)) => Some(&6);
+        grid_dense_out_of_bounds: DenseGrid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap().get((3, 0)) => None;
+        grid_dense_set: { let mut grid = DenseGrid::new(2, 2, 0); grid.set((1, 0), 7).unwrap(); grid.cells().map(|(_, cell)| *cell).collect::<Vec<_>>() } => vec![0, 7, 0, 0];
+        grid_dense_set_out_of_bounds: DenseGrid::new(2, 2, 0).set((2, 0), 7) => Err(OutOfBounds((2, 0)));
+        grid_dense_ragged_rows: DenseGrid::from_rows(vec![vec![1, 2], vec![3]]) => Err(RaggedRow { row: 1, expected: 2, found: 1 });
+        grid_dense_map: DenseGrid::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap().map(|(x, y), cell| cell * 10 + x + y) => DenseGrid::from_rows(vec![vec![10, 21], vec![31, 42]]).unwrap();
+        grid_dense_cells_positions: DenseGrid::new(2, 2, 0).cells().map(|(pos, _)| pos).collect::<Vec<_>>() => vec![(0, 0), (1, 0), (0, 1), (1, 1)];
+        grid_dense_render: DenseGrid::from_rows(vec![vec![1, 0], vec![0, 1]]).unwrap().render(|cell| if cell == Some(&1) { '#' } else { '.' }) => "#.\n.#\n";
+
+        journal_len_counts_executed_steps: journaled(vec![1, 0, 0, 0, 1, 0, 0, 0, 99], &[]).journal_len() => 2;
+        journal_len_without_journal: computer_one(vec![1, 0, 0, 0, 99]).computer.journal_len() => 0;
+        step_back_1_undoes_last_write: stringify(step_back(journaled(vec![1, 0, 0, 0, 1, 0, 0, 0, 99], &[]), 1).instructions) => "2,0,0,0,1,0,0,0,99";
+        step_back_all_restores_program: stringify(step_back(journaled(vec![1, 1, 1, 4, 99, 5, 6, 0, 99], &[]), 10).instructions) => "1,1,1,4,99,5,6,0,99";
+        step_back_exact: journaled(vec![1, 0, 0, 0, 1, 0, 0, 0, 99], &[]).step_back(2) => Ok(());
+        step_back_reports_exhausted_journal: journaled(vec![1, 0, 0, 0, 99], &[]).step_back(10) => Err(StepBackError::JournalExhausted { undone: 1 });
+        step_back_without_journal: computer_one(vec![1, 0, 0, 0, 99]).computer.step_back(1) => Err(StepBackError::NoJournal);
+        step_back_restores_ptr: step_back(journaled(vec![1, 0, 0, 0, 1, 0, 0, 0, 99], &[]), 1).ptr => 4;
+        step_back_restores_input: step_back(journaled(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], &[8]), 3).input.pop_front() => Some(8);
+        step_back_restores_relative_base: step_back(journaled(vec![109, 1000, 203, 5, 4, 1005, 99], &[42]), 3).relative_base => 0;
+        step_back_removes_extended_memory: step_back(journaled(vec![109, 1000, 203, 5, 4, 1005, 99], &[42]), 2).memory.get(&1005).copied() => None;
+        step_back_replays_same_outputs: step_back(journaled(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], &[8]), 2).run() => vec![1];
+        step_back_until_written_0: step_back_until_written(journaled(vec![1, 0, 0, 0, 1, 0, 0, 0, 99], &[]), 0) => (Some(1), 4);
+        step_back_until_written_9: step_back_until_written(journaled(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], &[7]), 9) => (Some(2), 2);
+        step_back_until_written_never: step_back_until_written(journaled(vec![1, 0, 0, 0, 99], &[]), 3) => (None, 4);
+        parse_arg_default: parse_arg(&args(&["day11", "fleet"]), 2, 2) => Ok(2);
+        parse_arg_given: parse_arg(&args(&["day11", "fleet", "5"]), 2, 2) => Ok(5);
+        parse_arg_invalid: parse_arg::<usize>(&args(&["day11", "fleet", "-1"]), 2, 2) => Err(UsageError::InvalidArgument { position: 2, found: "-1".to_string() });
+        parse_color_arg_default: parse_color_arg(&args(&["day11", "replay"]), 2, Color::White) => Ok(Color::White);
+        parse_color_arg_digits: (parse_color_arg(&args(&["day11", "replay", "0"]), 2, Color::White), parse_color_arg(&args(&["day11", "stats", "1"]), 2, Color::Black)) => (Ok(Color::Black), Ok(Color::White));
+        parse_color_arg_names: (parse_color_arg(&args(&["day11", "replay", "black"]), 2, Color::White), parse_color_arg(&args(&["day11", "stats", "white"]), 2, Color::Black)) => (Ok(Color::Black), Ok(Color::White));
+        parse_color_arg_unknown: parse_color_arg(&args(&["day11", "replay", "2"]), 2, Color::White) => Err(UsageError::UnknownColor("2".to_string()));
     }
 }
 
@@ -1,49 +1,87 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 use std::ops::Rem;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "nbody" {
+        let ticks = args.get(2).map_or(1000, |ticks| ticks.parse().unwrap());
+        let input = args.get(3).map_or(PUZZLE_INPUT.to_string(), |path| {
+            std::fs::read_to_string(path).unwrap()
+        });
+        let mut system = NBody::parse(&input);
+        println!(
+            "{} bodies in {} dimensions",
+            system.bodies(),
+            system.dimensions()
+        );
+        let periods = system.axis_periods();
+        println!("axis periods: {:?}", periods);
+        match combine_periods(&periods) {
+            Ok(period) => println!("period: {}", period),
+            Err(err) => println!("period: {:?}", err),
+        }
+        system.steps(ticks);
+        for body in 0..system.bodies() {
+            println!(
+                "body {}: pos {:?} vel {:?}",
+                body,
+                system.position(body),
+                system.velocity(body)
+            );
+        }
+        println!("energy after {} ticks: {}", ticks, system.energy());
+        return;
+    }
+    if args.len() > 1 && args[1] == "cycle" {
+        let ticks = args.get(2).map_or(0, |ticks| ticks.parse().unwrap());
+        let input = args.get(3).map_or(PUZZLE_INPUT.to_string(), |path| {
+            std::fs::read_to_string(path).unwrap()
+        });
+        let mut system = NBody::parse(&input);
+        system.steps(ticks);
+        let cycle = match system.cycle() {
+            Ok(cycle) => cycle,
+            Err(err) => {
+                println!("cycle: {:?}", err);
+                return;
+            }
+        };
+        for (axis, axis_cycle) in cycle.axes.iter().enumerate() {
+            println!(
+                "axis {}: tail {} period {}",
+                axis, axis_cycle.tail, axis_cycle.period
+            );
+        }
+        println!("tail: {} period: {}", cycle.tail, cycle.period);
+        match cycle.first_repeat() {
+            Some(step) => println!(
+                "snapshot after {} ticks first repeats a seen state {} ticks later (state of tick {})",
+                ticks,
+                step,
+                ticks + cycle.tail
+            ),
+            None => println!("first repeat overflows"),
+        }
+        return;
+    }
+
     let energy = calculate_energy(run_simulation(PUZZLE_INPUT, 1000));
     println!("1. energy: {}", energy);
 
-    let ticks = run_until_first_state(PUZZLE_INPUT);
-    println!("2. ticks: {}", ticks);
+    match run_until_first_state(PUZZLE_INPUT) {
+        Ok(ticks) => println!("2. ticks: {}", ticks),
+        Err(err) => println!("2. ticks: {:?}", err),
+    }
 }
 
-fn run_until_first_state(input: &str) -> usize {
+fn run_until_first_state(input: &str) -> Result<u128, PeriodError> {
     let moons: Vec<_> = parse_vectors(input)
         .into_iter()
         .map(Moon::new_not_moving)
         .collect();
-    let couples = unique_couples(&(0..moons.len()).collect::<Vec<usize>>());
-    let soa_moons = Moons::from_aos(moons);
-    let initial_state = soa_moons.clone();
-    let Moons {
-        mut pos_x,
-        mut vel_x,
-        mut pos_y,
-        mut vel_y,
-        mut pos_z,
-        mut vel_z,
-    } = soa_moons;
-    let ticks_x = run_until_match_other(
-        &couples,
-        &mut pos_x,
-        &mut vel_x,
-        (&initial_state.pos_x, &initial_state.vel_x),
-    );
-    let ticks_y = run_until_match_other(
-        &couples,
-        &mut pos_y,
-        &mut vel_y,
-        (&initial_state.pos_y, &initial_state.vel_y),
-    );
-    let ticks_z = run_until_match_other(
-        &couples,
-        &mut pos_z,
-        &mut vel_z,
-        (&initial_state.pos_z, &initial_state.vel_z),
-    );
-    lcm(lcm(ticks_x, ticks_y), ticks_z)
+    NBody::from_moons(&moons).period()
 }
 
 fn run_until_match_other(
@@ -63,8 +101,95 @@ fn run_until_match_other(
     ticks
 }
 
-fn lcm(x: usize, y: usize) -> usize {
-    x * y / gcd(x, y)
+#[derive(Debug, PartialEq, Clone)]
+enum PeriodError {
+    Overflow { periods: Vec<usize> },
+}
+
+fn lcm(x: u128, y: u128) -> Option<u128> {
+    (x / gcd(x, y)).checked_mul(y)
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct Cycle {
+    tail: usize,
+    period: usize,
+}
+
+fn state_hash<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn find_cycle<S, F>(start: &S, mut step: F) -> Cycle
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&mut S),
+{
+    let same = |a: &(u64, S), b: &(u64, S)| a.0 == b.0 && a.1 == b.1;
+    let advance = |state: &mut (u64, S), step: &mut F| {
+        step(&mut state.1);
+        state.0 = state_hash(&state.1);
+    };
+
+    let initial = (state_hash(start), start.clone());
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    advance(&mut hare, &mut step);
+    while !same(&tortoise, &hare) {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        advance(&mut hare, &mut step);
+        period += 1;
+    }
+
+    let mut tail = 0;
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..period {
+        advance(&mut hare, &mut step);
+    }
+    while !same(&tortoise, &hare) {
+        advance(&mut tortoise, &mut step);
+        advance(&mut hare, &mut step);
+        tail += 1;
+    }
+    Cycle { tail, period }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct SystemCycle {
+    tail: usize,
+    period: u128,
+    axes: Vec<Cycle>,
+}
+
+impl SystemCycle {
+    fn from_axes(axes: Vec<Cycle>) -> Result<SystemCycle, PeriodError> {
+        let tail = axes.iter().map(|axis| axis.tail).max().unwrap_or(0);
+        let periods: Vec<_> = axes.iter().map(|axis| axis.period).collect();
+        let period = combine_periods(&periods)?;
+        Ok(SystemCycle { tail, period, axes })
+    }
+
+    pub fn first_repeat(&self) -> Option<u128> {
+        (self.tail as u128).checked_add(self.period)
+    }
+}
+
+fn combine_periods(periods: &[usize]) -> Result<u128, PeriodError> {
+    periods
+        .iter()
+        .try_fold(1, |acc, &period| lcm(acc, period as u128))
+        .ok_or_else(|| PeriodError::Overflow {
+            periods: periods.to_vec(),
+        })
 }
 
 pub trait NonNegativeInteger: Copy + PartialOrd + Rem<Output = Self> + From<u8> {}
@@ -108,11 +233,8 @@ fn unique_couples(slice: &[usize]) -> Vec<(usize, usize)> {
 fn calculate_energy(moons: Vec<Moon>) -> V3DComponent {
     moons
         .into_iter()
-        .map(|m| {
-            (m.pos.x.abs() + m.pos.y.abs() + m.pos.z.abs())
-                * (m.vel.x.abs() + m.vel.y.abs() + m.vel.z.abs())
-        })
-        .fold(0 as V3DComponent, std::ops::Add::add)
+        .map(|m| m.pos.manhattan() * m.vel.manhattan())
+        .sum()
 }
 
 fn run_simulation(input: &str, total_ticks: usize) -> Vec<Moon> {
@@ -131,32 +253,12 @@ fn run_simulation(input: &str, total_ticks: usize) -> Vec<Moon> {
 
 fn iterate_couples(couples: &[(usize, usize)], moons: &mut [Moon]) {
     for (i, j) in couples {
-        if moons[*j].pos.x > moons[*i].pos.x {
-            moons[*i].vel.x += 1;
-            moons[*j].vel.x -= 1;
-        } else if moons[*j].pos.x < moons[*i].pos.x {
-            moons[*i].vel.x -= 1;
-            moons[*j].vel.x += 1;
-        }
-        if moons[*j].pos.y > moons[*i].pos.y {
-            moons[*i].vel.y += 1;
-            moons[*j].vel.y -= 1;
-        } else if moons[*j].pos.y < moons[*i].pos.y {
-            moons[*i].vel.y -= 1;
-            moons[*j].vel.y += 1;
-        }
-        if moons[*j].pos.z > moons[*i].pos.z {
-            moons[*i].vel.z += 1;
-            moons[*j].vel.z -= 1;
-        } else if moons[*j].pos.z < moons[*i].pos.z {
-            moons[*i].vel.z -= 1;
-            moons[*j].vel.z += 1;
-        }
+        let pull = (moons[*j].pos - moons[*i].pos).signum();
+        moons[*i].vel += pull;
+        moons[*j].vel -= pull;
     }
     for moon in moons.iter_mut() {
-        moon.pos.x += moon.vel.x;
-        moon.pos.y += moon.vel.y;
-        moon.pos.z += moon.vel.z;
+        moon.pos += moon.vel;
     }
 }
 
@@ -179,33 +281,157 @@ fn iterate_couples_split(
     }
 }
 
-#[derive(Clone)]
-struct Moons {
-    pos_x: Vec<V3DComponent>,
-    pos_y: Vec<V3DComponent>,
-    pos_z: Vec<V3DComponent>,
-    vel_x: Vec<V3DComponent>,
-    vel_y: Vec<V3DComponent>,
-    vel_z: Vec<V3DComponent>,
-}
-
-impl Moons {
-    pub fn from_aos(moons: Vec<Moon>) -> Self {
-        let pos_x = moons.iter().map(|m| m.pos.x).collect();
-        let pos_y = moons.iter().map(|m| m.pos.y).collect();
-        let pos_z = moons.iter().map(|m| m.pos.z).collect();
-        let vel_x = moons.iter().map(|m| m.vel.x).collect();
-        let vel_y = moons.iter().map(|m| m.vel.y).collect();
-        let vel_z = moons.iter().map(|m| m.vel.z).collect();
-        Moons {
-            pos_x,
-            pos_y,
-            pos_z,
-            vel_x,
-            vel_y,
-            vel_z,
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct AxisState {
+    pos: Vec<V3DComponent>,
+    vel: Vec<V3DComponent>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct NBody {
+    couples: Vec<(usize, usize)>,
+    axes: Vec<AxisState>,
+}
+
+impl NBody {
+    pub fn new(positions: Vec<Vec<V3DComponent>>) -> Self {
+        let dimensions = positions.first().map_or(0, Vec::len);
+        if positions.iter().any(|body| body.len() != dimensions) {
+            panic!("Bodies with different number of dimensions can't be simulated together.");
+        }
+        let axes = (0..dimensions)
+            .map(|axis| AxisState {
+                pos: positions.iter().map(|body| body[axis]).collect(),
+                vel: vec![0; positions.len()],
+            })
+            .collect();
+        NBody {
+            couples: unique_couples(&(0..positions.len()).collect::<Vec<usize>>()),
+            axes,
+        }
+    }
+
+    pub fn parse(input: &str) -> Self {
+        NBody::new(parse_bodies(input))
+    }
+
+    pub fn from_moons(moons: &[Moon]) -> Self {
+        let mut system = NBody::new(
+            moons
+                .iter()
+                .map(|moon| moon.pos.components().collect())
+                .collect(),
+        );
+        for (body, moon) in moons.iter().enumerate() {
+            for (axis, vel) in Axis::ALL.iter().map(|axis| moon.vel[*axis]).enumerate() {
+                system.axes[axis].vel[body] = vel;
+            }
+        }
+        system
+    }
+
+    pub fn bodies(&self) -> usize {
+        self.axes.first().map_or(0, |axis| axis.pos.len())
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.axes.len()
+    }
+
+    pub fn position(&self, body: usize) -> Vec<V3DComponent> {
+        self.axes.iter().map(|axis| axis.pos[body]).collect()
+    }
+
+    pub fn velocity(&self, body: usize) -> Vec<V3DComponent> {
+        self.axes.iter().map(|axis| axis.vel[body]).collect()
+    }
+
+    pub fn step(&mut self) {
+        for axis in self.axes.iter_mut() {
+            iterate_couples_split(&self.couples, &mut axis.pos, &mut axis.vel);
+        }
+    }
+
+    pub fn steps(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.step();
         }
     }
+
+    pub fn energy(&self) -> V3DComponent {
+        (0..self.bodies())
+            .map(|body| {
+                let potential: V3DComponent =
+                    self.axes.iter().map(|axis| axis.pos[body].abs()).sum();
+                let kinetic: V3DComponent = self.axes.iter().map(|axis| axis.vel[body].abs()).sum();
+                potential * kinetic
+            })
+            .sum()
+    }
+
+    pub fn axis_period(&self, axis: usize) -> usize {
+        let initial = &self.axes[axis];
+        let mut state = initial.clone();
+        run_until_match_other(
+            &self.couples,
+            &mut state.pos,
+            &mut state.vel,
+            (&initial.pos, &initial.vel),
+        )
+    }
+
+    pub fn axis_periods(&self) -> Vec<usize> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.dimensions())
+                .map(|axis| scope.spawn(move || self.axis_period(axis)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    pub fn period(&self) -> Result<u128, PeriodError> {
+        combine_periods(&self.axis_periods())
+    }
+
+    pub fn axis_cycle(&self, axis: usize) -> Cycle {
+        find_cycle(&self.axes[axis], |state| {
+            iterate_couples_split(&self.couples, &mut state.pos, &mut state.vel)
+        })
+    }
+
+    pub fn cycle(&self) -> Result<SystemCycle, PeriodError> {
+        let axes = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.dimensions())
+                .map(|axis| scope.spawn(move || self.axis_cycle(axis)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        SystemCycle::from_axes(axes)
+    }
+}
+
+fn parse_bodies(input: &str) -> Vec<Vec<V3DComponent>> {
+    input
+        .lines()
+        .map(|line| line.trim().trim_start_matches('<').trim_end_matches('>'))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|component| {
+                    let value = component.rsplit('=').next().unwrap().trim();
+                    value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("Wrong vector component: {}", component))
+                })
+                .collect()
+        })
+        .collect()
 }
 
 pub fn are_moons_in_equal_state(
@@ -323,46 +549,144 @@ fn parse_number(line: &[char], mut i: usize) -> (Option<V3DComponent>, usize) {
 
 type V3DComponent = i32;
 
-#[derive(Clone, Debug, PartialEq)]
-struct V3D {
-    x: V3DComponent,
-    y: V3DComponent,
-    z: V3DComponent,
+pub trait Component:
+    Copy
+    + Default
+    + PartialOrd
+    + std::fmt::Debug
+    + std::ops::Add<Output = Self>
+    + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self>
+    + std::ops::Neg<Output = Self>
+    + std::ops::AddAssign
+    + std::ops::SubAssign
+{
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
 }
 
-impl V3D {
-    pub fn new(x: V3DComponent, y: V3DComponent, z: V3DComponent) -> Self {
+macro_rules! impl_component {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Component for $t {
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+
+                fn signum(self) -> Self {
+                    <$t>::signum(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_component!(i8, i16, i32, i64, i128, isize);
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct V3D<T: Component = V3DComponent> {
+    x: T,
+    y: T,
+    z: T,
+}
+
+impl<T: Component> V3D<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
         V3D { x, y, z }
     }
+
+    pub fn manhattan(self) -> T {
+        self.x.abs() + self.y.abs() + self.z.abs()
+    }
+
+    pub fn signum(self) -> Self {
+        self.map(Component::signum)
+    }
+
+    pub fn map(self, f: impl Fn(T) -> T) -> Self {
+        V3D::new(f(self.x), f(self.y), f(self.z))
+    }
+
+    pub fn components(self) -> impl Iterator<Item = T> {
+        Axis::ALL.iter().map(move |axis| self[*axis])
+    }
 }
 
-impl std::ops::Mul<V3DComponent> for V3D {
-    type Output = V3D;
-    fn mul(mut self, rhs: V3DComponent) -> Self::Output {
-        self.x *= rhs;
-        self.y *= rhs;
-        self.z *= rhs;
-        self
+impl<T: Component> std::ops::Index<Axis> for V3D<T> {
+    type Output = T;
+    fn index(&self, axis: Axis) -> &Self::Output {
+        match axis {
+            Axis::X => &self.x,
+            Axis::Y => &self.y,
+            Axis::Z => &self.z,
+        }
+    }
+}
+
+impl<T: Component> std::ops::IndexMut<Axis> for V3D<T> {
+    fn index_mut(&mut self, axis: Axis) -> &mut Self::Output {
+        match axis {
+            Axis::X => &mut self.x,
+            Axis::Y => &mut self.y,
+            Axis::Z => &mut self.z,
+        }
     }
 }
 
-impl std::ops::Add for V3D {
-    type Output = V3D;
+impl<T: Component> std::ops::Mul<T> for V3D<T> {
+    type Output = V3D<T>;
+    fn mul(self, rhs: T) -> Self::Output {
+        self.map(|component| component * rhs)
+    }
+}
+
+impl<T: Component> std::ops::Add for V3D<T> {
+    type Output = V3D<T>;
     fn add(mut self, rhs: Self) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Component> std::ops::Sub for V3D<T> {
+    type Output = V3D<T>;
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl<T: Component> std::ops::Neg for V3D<T> {
+    type Output = V3D<T>;
+    fn neg(self) -> Self::Output {
+        self.map(|component| -component)
+    }
+}
+
+impl<T: Component> std::ops::AddAssign for V3D<T> {
+    fn add_assign(&mut self, rhs: Self) {
         self.x += rhs.x;
         self.y += rhs.y;
         self.z += rhs.z;
-        self
     }
 }
 
-impl std::ops::Sub for V3D {
-    type Output = V3D;
-    fn sub(mut self, rhs: Self) -> Self::Output {
+impl<T: Component> std::ops::SubAssign for V3D<T> {
+    fn sub_assign(&mut self, rhs: Self) {
         self.x -= rhs.x;
         self.y -= rhs.y;
         self.z -= rhs.z;
-        self
     }
 }
 
@@ -370,6 +694,22 @@ impl std::ops::Sub for V3D {
 mod test {
     use super::*;
 
+    fn brute_force_cycle(start: u64, step: impl Fn(u64) -> u64) -> Cycle {
+        let mut seen = std::collections::HashMap::new();
+        let mut state = start;
+        for tick in 0.. {
+            if let Some(&first) = seen.get(&state) {
+                return Cycle {
+                    tail: first,
+                    period: tick - first,
+                };
+            }
+            seen.insert(state, tick);
+            state = step(state);
+        }
+        unreachable!()
+    }
+
     macro_rules! eq_tests {
         ( $( $name:ident: $input:expr => $expected:expr;)* ) => {
             $(
@@ -397,6 +737,54 @@ mod test {
         example_iteration_0: run_simulation(FIXTURE_EXAMPLE_1, 0) => vec![moon(-1, 0,2,0,0,0), moon(2,-10,-7,0,0,0), moon(4,-8,8,0,0,0), moon(3,5,-1,0,0,0)];
         example_iteration_1: run_simulation(FIXTURE_EXAMPLE_1, 1) => vec![moon(2, -1,1,3,-1,-1), moon(3,-7,-4,1,3,3), moon(1,-7,5,-3,1,-3), moon(2,2,0,-1,-3,1)];
         example_energy_10: calculate_energy(run_simulation(FIXTURE_EXAMPLE_1, 10)) => 179;
+        part_1: calculate_energy(run_simulation(PUZZLE_INPUT, 1000)) => 7179;
+        v3d_manhattan: V3D::new(-3, 4, -5).manhattan() => 12;
+        v3d_signum: V3D::new(-3, 0, 5).signum() => V3D::new(-1, 0, 1);
+        v3d_neg: -V3D::new(-3, 0, 5) => V3D::new(3, 0, -5);
+        v3d_assign_ops: { let mut vec = V3D::new(1, 2, 3); vec += V3D::new(10, 20, 30); vec -= V3D::new(1, 1, 1); vec } => V3D::new(10, 21, 32);
+        v3d_ops: (V3D::new(1, 2, 3) + V3D::new(1, 1, 1) - V3D::new(0, 2, 0)) * 2 => V3D::new(4, 2, 8);
+        v3d_index: Axis::ALL.iter().map(|axis| V3D::new(7, 8, 9)[*axis]).collect::<Vec<_>>() => vec![7, 8, 9];
+        v3d_index_mut: { let mut vec = V3D::new(7, 8, 9); vec[Axis::Y] = -1; vec } => V3D::new(7, -1, 9);
+        v3d_components: V3D::new(7, 8, 9).components().collect::<Vec<_>>() => vec![7, 8, 9];
+        v3d_generic_i64: V3D::<i64>::new(3_000_000_000, -1, 0).manhattan() => 3_000_000_001;
+        v3d_generic_i128_signum: V3D::<i128>::new(-5, 0, 5).signum() => V3D::new(-1, 0, 1);
+        v3d_default: V3D::<i32>::default() => V3D::new(0, 0, 0);
+        part_2: run_until_first_state(PUZZLE_INPUT) => Ok(428576638953552);
+        example_period_1: run_until_first_state(FIXTURE_EXAMPLE_1) => Ok(2772);
+        example_period_2: run_until_first_state(FIXTURE_EXAMPLE_2) => Ok(4686774924);
+        nbody_parse_3d: NBody::parse(FIXTURE_EXAMPLE_1) => NBody::from_moons(&run_simulation(FIXTURE_EXAMPLE_1, 0));
+        nbody_shape: { let system = NBody::parse(FIXTURE_EXAMPLE_1); (system.bodies(), system.dimensions()) } => (4, 3);
+        nbody_energy_matches_moons: { let mut system = NBody::parse(FIXTURE_EXAMPLE_1); system.steps(10); system.energy() } => 179;
+        nbody_state_matches_moons: { let mut system = NBody::parse(FIXTURE_EXAMPLE_1); system.steps(1); (system.position(0), system.velocity(0)) } => (vec![2, -1, 1], vec![3, -1, -1]);
+        nbody_from_moons_velocity: NBody::from_moons(&run_simulation(FIXTURE_EXAMPLE_1, 1)) => { let mut system = NBody::parse(FIXTURE_EXAMPLE_1); system.step(); system };
+        nbody_period_3d: NBody::parse(FIXTURE_EXAMPLE_2).period() => Ok(4686774924);
+        nbody_axis_periods: { let system = NBody::parse(FIXTURE_EXAMPLE_2); (0..3).map(|axis| system.axis_period(axis)).collect::<Vec<_>>() } => vec![2028, 5898, 4702];
+        nbody_1d: { let mut system = NBody::parse("<x=0>\n<x=1>"); let period = system.period(); system.step(); (period, system.position(0), system.position(1), system.energy()) } => (Ok(4), vec![1], vec![0], 1);
+        nbody_2d: NBody::parse("<x=-1, y=0>\n<x=2, y=-10>\n<x=4, y=-8>\n<x=3, y=5>").period() => Ok(252);
+        nbody_4d: NBody::parse("<x=-1, y=0, z=2, w=0>\n<x=2, y=-10, z=-7, w=1>\n<x=4, y=-8, z=8, w=2>\n<x=3, y=5, z=-1, w=3>").period() => Ok(2772);
+        nbody_many_bodies: NBody::parse("<x=0>\n<x=3>\n<x=5>\n<x=9>\n<x=12>\n<x=-4>\n<x=7>").couples.len() => 21;
+        nbody_single_body: { let mut system = NBody::parse("<x=5, y=-3>"); system.steps(10); (system.position(0), system.period()) } => (vec![5, -3], Ok(1));
+        nbody_axis_periods_parallel: NBody::parse(FIXTURE_EXAMPLE_2).axis_periods() => vec![2028, 5898, 4702];
+        nbody_axis_periods_4d: NBody::parse("<x=-1, y=0, z=2, w=0>\n<x=2, y=-10, z=-7, w=1>\n<x=4, y=-8, z=8, w=2>\n<x=3, y=5, z=-1, w=3>").axis_periods() => vec![18, 28, 44, 4];
+        lcm_coprime: lcm(4, 9) => Some(36);
+        lcm_shared_factor: lcm(2028, 5898) => Some(1993524);
+        lcm_beyond_u64: lcm(u64::MAX as u128, 2) => Some(u64::MAX as u128 * 2);
+        lcm_overflow: lcm(u128::MAX, 2) => None;
+        combine_periods_empty: combine_periods(&[]) => Ok(1);
+        combine_periods_example: combine_periods(&[2028, 5898, 4702]) => Ok(4686774924);
+        combine_periods_beyond_usize: combine_periods(&[usize::MAX, usize::MAX - 1]) => Ok(usize::MAX as u128 * (usize::MAX - 1) as u128);
+        combine_periods_overflow: combine_periods(&[usize::MAX, usize::MAX - 1, usize::MAX - 2]) => Err(PeriodError::Overflow { periods: vec![usize::MAX, usize::MAX - 1, usize::MAX - 2] });
+        find_cycle_pure: find_cycle(&0u32, |x| *x = (*x + 1) % 7) => Cycle { tail: 0, period: 7 };
+        find_cycle_fixed_point: find_cycle(&"still".to_string(), |_| {}) => Cycle { tail: 0, period: 1 };
+        find_cycle_with_tail: find_cycle(&0u32, |x| *x = if *x < 5 { *x + 1 } else { 5 + (*x - 4) % 3 }) => Cycle { tail: 5, period: 3 };
+        find_cycle_long_tail: find_cycle(&3u64, |x| *x = (*x * *x + 1) % 255) => brute_force_cycle(3, |x| (x * x + 1) % 255);
+        find_cycle_counts_steps: { let mut steps = 0; find_cycle(&0u32, |x| { steps += 1; *x = (*x + 1) % 100 }); steps > 100 } => true;
+        nbody_axis_cycles: { let system = NBody::parse(FIXTURE_EXAMPLE_2); (0..3).map(|axis| system.axis_cycle(axis)).collect::<Vec<_>>() } => vec![Cycle { tail: 0, period: 2028 }, Cycle { tail: 0, period: 5898 }, Cycle { tail: 0, period: 4702 }];
+        nbody_cycle: NBody::parse(FIXTURE_EXAMPLE_1).cycle().map(|cycle| (cycle.tail, cycle.period, cycle.first_repeat())) => Ok((0, 2772, Some(2772)));
+        nbody_cycle_from_snapshot: { let mut system = NBody::parse(FIXTURE_EXAMPLE_2); system.steps(1000); system.cycle().map(|cycle| (cycle.tail, cycle.period)) } => Ok((0, 4686774924));
+        nbody_cycle_returns_to_snapshot: { let mut system = NBody::parse(FIXTURE_EXAMPLE_1); system.steps(500); let snapshot = system.clone(); let repeat = system.cycle().unwrap().first_repeat().unwrap(); system.steps(repeat as usize); system == snapshot } => true;
+        system_cycle_tail_is_longest: SystemCycle::from_axes(vec![Cycle { tail: 2, period: 4 }, Cycle { tail: 7, period: 6 }]).map(|cycle| (cycle.tail, cycle.period, cycle.first_repeat())) => Ok((7, 12, Some(19)));
+        system_cycle_overflow: SystemCycle::from_axes(vec![Cycle { tail: 0, period: usize::MAX }, Cycle { tail: 0, period: usize::MAX - 1 }, Cycle { tail: 0, period: usize::MAX - 2 }]).is_err() => true;
         unique_couples_1: unique_couples(&vec![0, 1, 2]) => vec![(0, 1), (0, 2), (1, 2)];
     }
 }
@@ -408,6 +796,14 @@ const FIXTURE_EXAMPLE_1: &str = "
 <x=3, y=5, z=-1>
 ";
 
+#[cfg(test)]
+const FIXTURE_EXAMPLE_2: &str = "
+<x=-8, y=-10, z=0>
+<x=5, y=5, z=10>
+<x=2, y=-7, z=3>
+<x=9, y=-8, z=-3>
+";
+
 const PUZZLE_INPUT: &str = "
 <x=3, y=15, z=8>
 <x=5, y=-1, z=-2>
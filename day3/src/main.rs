@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use grid::{Bounds, Grid, Pos, SparseGrid};
 
 type Step = u64;
 type Coordinate = (i64, i64, Step);
@@ -58,21 +58,20 @@ enum Wire {
 }
 
 fn step_trough_matrix(
-    matrix: &mut HashMap<(i64, i64), Wire>,
-    y: i64,
-    x: i64,
+    matrix: &mut SparseGrid<Wire>,
+    pos: Pos,
     id: i8,
     coords: &mut Vec<Coordinate>,
     step: Step,
 ) {
-    match matrix.get(&(y, x)) {
+    match matrix.get(pos).copied() {
         None => {
-            matrix.insert((y, x), Wire::Pass(id, step));
+            matrix.set(pos, Wire::Pass(id, step));
         }
         Some(Wire::Pass(other_id, other_step)) => {
-            if id != *other_id {
-                coords.push((y, x, step + *other_step));
-                matrix.insert((y, x), Wire::Cross);
+            if id != other_id {
+                coords.push((pos.1, pos.0, step + other_step));
+                matrix.set(pos, Wire::Cross);
             }
         }
         Some(Wire::Cross) => panic!("Cross over cross"),
@@ -81,14 +80,14 @@ fn step_trough_matrix(
 }
 
 fn start_tracing(
-    matrix: &mut HashMap<(i64, i64), Wire>,
+    matrix: &mut SparseGrid<Wire>,
     segments: &[Segment],
     id: i8,
     coords: &mut Vec<Coordinate>,
 ) {
     let mut x = 0;
     let mut y = 0;
-    matrix.insert((y, x), Wire::Origin);
+    matrix.set((x, y), Wire::Origin);
     let mut step = 0;
     for Segment { travel, dir } in segments {
         for _ in 0 .. *travel {
@@ -99,47 +98,27 @@ fn start_tracing(
                 Direction::D => y += 1,
             }
             step += 1;
-            step_trough_matrix(matrix, y, x, id, coords, step);
+            step_trough_matrix(matrix, (x, y), id, coords, step);
         }
     }
 }
 
 #[allow(dead_code)]
-fn print_matrix(matrix: &HashMap<(i64, i64), Wire>) {
-    let mut max: (i64, i64) = (std::i64::MIN, std::i64::MIN);
-    let mut min: (i64, i64) = (std::i64::MAX, std::i64::MAX);
-    for (x, y) in matrix.keys() {
-        if *x < min.0 {
-            min.0 = *x;
-        }
-        if *y < min.1 {
-            min.1 = *y;
-        }
-        if *x > max.0 {
-            max.0 = *x;
-        }
-        if *y > max.1 {
-            max.1 = *y;
-        }
-    }
-    for y in min.1 - 2..max.1 + 2 {
-        for x in min.0 - 2..max.0 + 2 {
-            print!(
-                "{} ",
-                match matrix.get(&(y, x)) {
-                    None => String::from(" ."),
-                    Some(Wire::Pass(_, s)) => format!("{:02}", s),
-                    Some(Wire::Cross) => String::from(" x"),
-                    Some(Wire::Origin) => String::from(" @"),
-                }
-            );
-        }
-        println!();
+fn print_matrix(matrix: &SparseGrid<Wire>) {
+    if let Some(bounds) = matrix.bounds() {
+        print!("{}", render_matrix(matrix, bounds.expand(2)));
     }
 }
-
+fn render_matrix(matrix: &SparseGrid<Wire>, bounds: Bounds) -> String {
+    matrix.render_region(bounds, |wire| match wire {
+        None => String::from(" . "),
+        Some(Wire::Pass(_, s)) => format!("{:02} ", s),
+        Some(Wire::Cross) => String::from(" x "),
+        Some(Wire::Origin) => String::from(" @ "),
+    })
+}
 fn cross_segments(first: &[Segment], second: &[Segment]) -> (i64, i64) {
-    let mut matrix = HashMap::new();
+    let mut matrix = SparseGrid::new();
     let mut crosses: Vec<Coordinate> = vec![];
     start_tracing(&mut matrix, first, 1, &mut crosses);
     start_tracing(&mut matrix, second, 2, &mut crosses);
@@ -158,9 +137,332 @@ fn cross_segments(first: &[Segment], second: &[Segment]) -> (i64, i64) {
     (min_distance,     min_timing)
 }
 
+mod grid {
+    use std::collections::HashMap;
+    use std::fmt::Display;
+
+    pub type Pos = (i64, i64);
+
+    #[allow(dead_code)]
+    const NEIGHBORS: [Pos; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    #[allow(dead_code)]
+    const DIAGONAL_NEIGHBORS: [Pos; 8] = [
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+    ];
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    pub struct Bounds {
+        pub min_x: i64,
+        pub min_y: i64,
+        pub max_x: i64,
+        pub max_y: i64,
+    }
+
+    impl Bounds {
+        pub fn new(pos: Pos) -> Self {
+            Bounds {
+                min_x: pos.0,
+                min_y: pos.1,
+                max_x: pos.0,
+                max_y: pos.1,
+            }
+        }
+
+        pub fn include(self, pos: Pos) -> Self {
+            Bounds {
+                min_x: self.min_x.min(pos.0),
+                min_y: self.min_y.min(pos.1),
+                max_x: self.max_x.max(pos.0),
+                max_y: self.max_y.max(pos.1),
+            }
+        }
+
+        pub fn expand(self, margin: i64) -> Self {
+            Bounds {
+                min_x: self.min_x - margin,
+                min_y: self.min_y - margin,
+                max_x: self.max_x + margin,
+                max_y: self.max_y + margin,
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn contains(self, pos: Pos) -> bool {
+            pos.0 >= self.min_x && pos.0 <= self.max_x && pos.1 >= self.min_y && pos.1 <= self.max_y
+        }
+
+        #[allow(dead_code)]
+        pub fn width(self) -> i64 {
+            self.max_x - self.min_x + 1
+        }
+
+        #[allow(dead_code)]
+        pub fn height(self) -> i64 {
+            self.max_y - self.min_y + 1
+        }
+
+        #[allow(dead_code)]
+        pub fn positions(self) -> impl Iterator<Item = Pos> {
+            (self.min_y..=self.max_y)
+                .flat_map(move |y| (self.min_x..=self.max_x).map(move |x| (x, y)))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    #[allow(dead_code)]
+    pub struct RaggedRow {
+        pub row: usize,
+        pub expected: usize,
+        pub found: usize,
+    }
+
+    #[derive(Debug, PartialEq, Copy, Clone)]
+    #[allow(dead_code)]
+    pub struct OutOfBounds(pub Pos);
+
+    pub trait Grid<T> {
+        fn get(&self, pos: Pos) -> Option<&T>;
+        #[allow(dead_code)]
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T>;
+        fn bounds(&self) -> Option<Bounds>;
+        #[allow(dead_code)]
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a>;
+
+        #[allow(dead_code)]
+        fn neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn diagonal_neighbors(&self, pos: Pos) -> Vec<(Pos, &T)> {
+            self.around(pos, &DIAGONAL_NEIGHBORS)
+        }
+
+        #[allow(dead_code)]
+        fn around(&self, pos: Pos, offsets: &[Pos]) -> Vec<(Pos, &T)> {
+            offsets
+                .iter()
+                .map(|(dx, dy)| (pos.0 + dx, pos.1 + dy))
+                .filter_map(|neighbor| self.get(neighbor).map(|cell| (neighbor, cell)))
+                .collect()
+        }
+
+        #[allow(dead_code)]
+        fn render<D: Display>(&self, cell: impl Fn(Option<&T>) -> D) -> String {
+            match self.bounds() {
+                Some(bounds) => self.render_region(bounds, cell),
+                None => String::new(),
+            }
+        }
+
+        fn render_region<D: Display>(
+            &self,
+            bounds: Bounds,
+            cell: impl Fn(Option<&T>) -> D,
+        ) -> String {
+            let mut text = String::new();
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    text += &cell(self.get((x, y))).to_string();
+                }
+                text.push('\n');
+            }
+            text
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct SparseGrid<T> {
+        cells: HashMap<Pos, T>,
+        bounds: Option<Bounds>,
+    }
+
+    impl<T> SparseGrid<T> {
+        pub fn new() -> Self {
+            SparseGrid {
+                cells: HashMap::new(),
+                bounds: None,
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn len(&self) -> usize {
+            self.cells.len()
+        }
+
+        #[allow(dead_code)]
+        pub fn is_empty(&self) -> bool {
+            self.cells.is_empty()
+        }
+
+        #[allow(dead_code)]
+        pub fn contains(&self, pos: Pos) -> bool {
+            self.cells.contains_key(&pos)
+        }
+
+        pub fn set(&mut self, pos: Pos, value: T) {
+            self.include(pos);
+            self.cells.insert(pos, value);
+        }
+
+        #[allow(dead_code)]
+        pub fn get_or_insert_with(&mut self, pos: Pos, value: impl FnOnce() -> T) -> &mut T {
+            self.include(pos);
+            self.cells.entry(pos).or_insert_with(value)
+        }
+
+        fn include(&mut self, pos: Pos) {
+            self.bounds = Some(match self.bounds {
+                Some(bounds) => bounds.include(pos),
+                None => Bounds::new(pos),
+            });
+        }
+    }
+
+    impl<T> Default for SparseGrid<T> {
+        fn default() -> Self {
+            SparseGrid::new()
+        }
+    }
+
+    impl<T> Grid<T> for SparseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.cells.get(&pos)
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            self.cells.get_mut(&pos)
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            self.bounds
+        }
+
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            Box::new(self.cells.iter().map(|(pos, cell)| (*pos, cell)))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    #[allow(dead_code)]
+    pub struct DenseGrid<T> {
+        width: usize,
+        height: usize,
+        cells: Vec<T>,
+    }
+
+    #[allow(dead_code)]
+    impl<T> DenseGrid<T> {
+        pub fn new(width: usize, height: usize, fill: T) -> Self
+        where
+            T: Clone,
+        {
+            DenseGrid {
+                width,
+                height,
+                cells: vec![fill; width * height],
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, RaggedRow> {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if let Some((row, found)) = rows
+                .iter()
+                .map(Vec::len)
+                .enumerate()
+                .find(|(_, len)| *len != width)
+            {
+                return Err(RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+            Ok(DenseGrid {
+                width,
+                height,
+                cells: rows.into_iter().flatten().collect(),
+            })
+        }
+
+        pub fn map<U>(&self, cell: impl Fn(Pos, &T) -> U) -> DenseGrid<U> {
+            DenseGrid {
+                width: self.width,
+                height: self.height,
+                cells: self.cells().map(|(pos, value)| cell(pos, value)).collect(),
+            }
+        }
+
+        pub fn set(&mut self, pos: Pos, value: T) -> Result<(), OutOfBounds> {
+            let index = self.index(pos).ok_or(OutOfBounds(pos))?;
+            self.cells[index] = value;
+            Ok(())
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        fn index(&self, pos: Pos) -> Option<usize> {
+            if pos.0 < 0 || pos.1 < 0 || pos.0 >= self.width as i64 || pos.1 >= self.height as i64 {
+                None
+            } else {
+                Some(pos.0 as usize + pos.1 as usize * self.width)
+            }
+        }
+    }
+
+    impl<T> Grid<T> for DenseGrid<T> {
+        fn get(&self, pos: Pos) -> Option<&T> {
+            self.index(pos).map(|index| &self.cells[index])
+        }
+
+        fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+            match self.index(pos) {
+                Some(index) => Some(&mut self.cells[index]),
+                None => None,
+            }
+        }
+
+        fn bounds(&self) -> Option<Bounds> {
+            if self.cells.is_empty() {
+                None
+            } else {
+                Some(Bounds {
+                    min_x: 0,
+                    min_y: 0,
+                    max_x: self.width as i64 - 1,
+                    max_y: self.height as i64 - 1,
+                })
+            }
+        }
+
+        fn cells<'a>(&'a self) -> Box<dyn Iterator<Item = (Pos, &'a T)> + 'a> {
+            let width = self.width;
+            Box::new(
+                self.cells.iter().enumerate().map(move |(index, cell)| {
+                    (((index % width) as i64, (index / width) as i64), cell)
+                }),
+            )
+        }
+    }
+}
 #[cfg(test)]
 mod test {
 
+    use super::grid::{DenseGrid, OutOfBounds, RaggedRow};
     use super::*;
 
     #[test]
@@ -206,6 +508,80 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_render_matrix() {
+        let mut matrix = SparseGrid::new();
+        let mut crosses = vec![];
+        start_tracing(&mut matrix, &parse("R2,D1"), 1, &mut crosses);
+        start_tracing(&mut matrix, &parse("D1,R2"), 2, &mut crosses);
+        assert_eq!(crosses, vec![(1, 2, 6)]);
+        assert_eq!(
+            render_matrix(&matrix, matrix.bounds().unwrap()),
+            " @ 01 02 \n01 02  x \n"
+        );
+    }
+    #[test]
+    fn test_grid_bounds() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.set((2, -3), 'a');
+        grid.set((-4, 5), 'b');
+        assert_eq!(
+            grid.bounds(),
+            Some(Bounds {
+                min_x: -4,
+                min_y: -3,
+                max_x: 2,
+                max_y: 5
+            })
+        );
+        assert_eq!(grid.bounds().unwrap().expand(1).width(), 9);
+        assert_eq!(grid.bounds().unwrap().expand(1).height(), 11);
+    }
+    #[test]
+    fn test_grid_neighbors() {
+        let mut grid = SparseGrid::new();
+        grid.set((0, 0), 'a');
+        grid.set((1, 0), 'b');
+        grid.set((1, 1), 'c');
+        grid.set((0, -1), 'd');
+        assert_eq!(
+            grid.neighbors((0, 0)),
+            vec![((0, -1), &'d'), ((1, 0), &'b')]
+        );
+        assert_eq!(
+            grid.diagonal_neighbors((0, 0)),
+            vec![((0, -1), &'d'), ((1, 0), &'b'), ((1, 1), &'c')]
+        );
+    }
+    #[test]
+    fn test_grid_render() {
+        let mut grid = SparseGrid::new();
+        grid.set((0, 0), 'a');
+        grid.set((2, 1), 'b');
+        assert_eq!(grid.render(|cell| *cell.unwrap_or(&'.')), "a..\n..b\n");
+        let dense = DenseGrid::from_rows(vec![vec![1, 0], vec![0, 1]]).unwrap();
+        assert_eq!(dense.get((1, 1)), Some(&1));
+        assert_eq!(dense.get((2, 0)), None);
+        assert_eq!(
+            dense.render(|cell| if cell == Some(&1) { '#' } else { '.' }),
+            "#.\n.#\n"
+        );
+    }
+    #[test]
+    fn test_grid_dense_errors() {
+        assert_eq!(
+            DenseGrid::from_rows(vec![vec![1, 2], vec![3]]),
+            Err(RaggedRow {
+                row: 1,
+                expected: 2,
+                found: 1
+            })
+        );
+        let mut dense = DenseGrid::new(2, 2, 0);
+        assert_eq!(dense.set((1, 1), 5), Ok(()));
+        assert_eq!(dense.set((-1, 0), 5), Err(OutOfBounds((-1, 0))));
+    }
     #[test]
     fn test_cross_steps_1() {
         assert_eq!(
@@ -1,8 +1,157 @@
+use std::io::{BufReader, Bytes, Read};
+
+use image::{Image, Palette, Rgb};
+
 fn main() {
-    let code = decode(PUZZLE_INPUT, 25, 6);
-    println!("1. code: {}", code);
+    let args: Vec<String> = std::env::args().collect();
+    if let Err(err) = run_command(&args) {
+        eprintln!("usage error: {:?}", err);
+        std::process::exit(2);
+    }
+}
+
+fn run_command(args: &[String]) -> Result<(), UsageError> {
+    if args.len() > 1 && args[1] == "export" {
+        let path = args.get(2).map_or("message.svg", String::as_str);
+        let scale = parse_arg(args, 3, 10)?;
+        let sif = SifImage::parse(PUZZLE_INPUT, 25, 6).unwrap();
+        message_image(&sif.composite(), 25, 6)
+            .with_scale(scale)
+            .save(path)
+            .unwrap();
+        println!("message written to {}", path);
+        return Ok(());
+    }
+
+    if args.len() > 1 && args[1] == "stream" {
+        let mut path = None;
+        let mut transparent = TRANSPARENT;
+        let mut stop_when_opaque = false;
+        for (position, arg) in args.iter().enumerate().skip(2) {
+            if arg == "--stop-when-opaque" {
+                stop_when_opaque = true;
+            } else if let Some(digit) = arg.strip_prefix("--transparent=") {
+                transparent = digit.parse().map_err(|_| UsageError::InvalidArgument {
+                    position,
+                    found: arg.clone(),
+                })?;
+            } else {
+                path = Some(arg);
+            }
+        }
+        let decoded = match path {
+            Some(path) => {
+                let file = std::fs::File::open(path)
+                    .map_err(|error| UsageError::Sif(SifError::Io(error.kind())))?;
+                configure_decoder(SifDecoder::new(file, 25, 6), transparent, stop_when_opaque)?
+                    .decode()
+            }
+            None => configure_decoder(
+                SifDecoder::new(PUZZLE_INPUT.as_bytes(), 25, 6),
+                transparent,
+                stop_when_opaque,
+            )?
+            .decode(),
+        }
+        .map_err(UsageError::Sif)?;
+        match decoded.checksum() {
+            Some(code) => println!("1. code: {}", code),
+            None => println!(
+                "1. code: unavailable, decoding stopped after {} layers",
+                decoded.layers_read()
+            ),
+        }
+        if let Some(layer) = decoded.opaque_after {
+            println!(
+                "   opaque after layer {} of {}",
+                layer,
+                decoded.layers_read()
+            );
+        }
+        println!(
+            "2. reads: {}",
+            ocr_image(&decoded.composite, decoded.width, decoded.height).unwrap_or_default()
+        );
+        return Ok(());
+    }
 
-    let picture = render(PUZZLE_INPUT, 25, 6);
+    let sif = SifImage::parse(PUZZLE_INPUT, 25, 6).unwrap();
+    if args.len() > 2 && args[1] == "layers" {
+        let n = bounded_arg(args, 2, sif.layer_count())?;
+        for row in sif.layer(n).unwrap_or_default().chunks(sif.width()) {
+            println!("{}", row.iter().map(u32::to_string).collect::<String>());
+        }
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "layers" {
+        println!(
+            "{} layers of {}x{}",
+            sif.layer_count(),
+            sif.width(),
+            sif.height()
+        );
+        for (n, stats) in sif.layer_stats().iter().enumerate() {
+            println!(
+                "layer {:3}: {:3} zeroes, {:3} ones, {:3} twos",
+                n,
+                stats.count(0),
+                stats.count(1),
+                stats.count(2)
+            );
+        }
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "pixel" {
+        let x = bounded_arg(args, 2, sif.width())?;
+        let y = bounded_arg(args, 3, sif.height())?;
+        let stack = (0..sif.layer_count()).map_while(|n| sif.pixel(n, x, y));
+        for (n, digit) in stack.enumerate() {
+            println!("layer {:3}: {}", n, digit);
+            if digit != TRANSPARENT {
+                break;
+            }
+        }
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "flatten" {
+        let flat = SifImage::from_layers(sif.width(), sif.height(), &[sif.composite()]).unwrap();
+        match args.get(2) {
+            Some(path) => {
+                std::fs::write(path, flat.encode()).unwrap();
+                println!("flattened image written to {}", path);
+            }
+            None => println!("{}", flat.encode()),
+        }
+        return Ok(());
+    }
+    if args.len() > 1 && args[1] == "color" {
+        let compositing = match args.get(2).map(String::as_str) {
+            Some("first") => Compositing::FirstOpaque,
+            _ => Compositing::Alpha,
+        };
+        let transparent = parse_arg(args, 3, TRANSPARENT)?;
+        let sif = sif.with_transparent(transparent).map_err(UsageError::Sif)?;
+        let mut palette = SifPalette::classic();
+        let mut path = None;
+        for (position, arg) in args.iter().enumerate().skip(4) {
+            match arg.split_once('=') {
+                Some(setting) => palette = parse_palette_arg(palette, position, setting)?,
+                None => path = Some(arg),
+            }
+        }
+        print!("{}", sif.to_ansi(&palette, compositing));
+        if let Some(path) = path {
+            sif.to_image(&palette, compositing)
+                .with_scale(10)
+                .save(path)
+                .unwrap();
+            println!("rendering written to {}", path);
+        }
+        return Ok(());
+    }
+    println!("1. code: {}", sif.checksum().unwrap());
+
+    let picture = sif.composite();
     println!("2. message:");
     for j in 0..6 {
         for i in 0..25 {
@@ -10,77 +159,864 @@ fn main() {
         }
         println!();
     }
+    println!(
+        "   reads: {}",
+        ocr_image(&picture, 25, 6).unwrap_or_default()
+    );
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum UsageError {
+    MissingArgument { position: usize },
+    InvalidArgument { position: usize, found: String },
+    Sif(SifError),
+}
+
+fn parse_arg<T: std::str::FromStr>(
+    args: &[String],
+    position: usize,
+    default: T,
+) -> Result<T, UsageError> {
+    match args.get(position) {
+        Some(_) => required_arg(args, position),
+        None => Ok(default),
+    }
+}
+
+fn required_arg<T: std::str::FromStr>(args: &[String], position: usize) -> Result<T, UsageError> {
+    let arg = args
+        .get(position)
+        .ok_or(UsageError::MissingArgument { position })?;
+    arg.parse().map_err(|_| UsageError::InvalidArgument {
+        position,
+        found: arg.clone(),
+    })
+}
+
+fn bounded_arg(args: &[String], position: usize, limit: usize) -> Result<usize, UsageError> {
+    let value = required_arg(args, position)?;
+    if value >= limit {
+        return Err(UsageError::InvalidArgument {
+            position,
+            found: args[position].clone(),
+        });
+    }
+    Ok(value)
+}
+
+fn configure_decoder<R: Read>(
+    decoder: SifDecoder<R>,
+    transparent: u32,
+    stop_when_opaque: bool,
+) -> Result<SifDecoder<R>, UsageError> {
+    let decoder = decoder
+        .with_transparent(transparent)
+        .map_err(UsageError::Sif)?;
+    Ok(if stop_when_opaque {
+        decoder.stop_when_opaque()
+    } else {
+        decoder
+    })
 }
 
-fn to_digits(data: &str) -> Vec<u32> {
-    let result: Vec<_> = data.chars().map(|c| c.to_digit(10).unwrap()).collect();
-    result
+fn parse_palette_arg(
+    palette: SifPalette,
+    position: usize,
+    (target, value): (&str, &str),
+) -> Result<SifPalette, UsageError> {
+    let invalid = || UsageError::InvalidArgument {
+        position,
+        found: format!("{}={}", target, value),
+    };
+    let (color, alpha) = parse_hex_color(value).ok_or_else(invalid)?;
+    if target == "bg" {
+        return match alpha {
+            None => Ok(palette.with_background(color)),
+            Some(_) => Err(invalid()),
+        };
+    }
+    let digit = target.parse().map_err(|_| invalid())?;
+    let palette = palette.with_color(digit, color).map_err(UsageError::Sif)?;
+    match alpha {
+        Some(alpha) => palette.with_alpha(digit, alpha).map_err(UsageError::Sif),
+        None => Ok(palette),
+    }
 }
 
-fn render(data: &str, width: usize, height: usize) -> Vec<u32> {
-    let digits = to_digits(data);
-    let num_layers = digits.len() / (width * height);
+fn parse_hex_color(text: &str) -> Option<(Rgb, Option<u8>)> {
+    if !text.chars().all(|c| c.is_ascii_hexdigit()) || (text.len() != 6 && text.len() != 8) {
+        return None;
+    }
+    let channel = |n: usize| u8::from_str_radix(&text[2 * n..2 * n + 2], 16).ok();
+    let color = [channel(0)?, channel(1)?, channel(2)?];
+    let alpha = if text.len() == 8 { channel(3) } else { None };
+    Some((color, alpha))
+}
 
-    let mut result: Vec<i32> = vec![-1; width * height];
-    for n in 0..num_layers {
-        for i in 0..width {
-            for j in 0..height {
-                let source = digits[i + width * j + n * width * height];
-                let target = &mut result[i + width * j];
-                if source != 2 && *target == -1 {
-                    *target = source as i32;
+const TRANSPARENT: u32 = 2;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum SifError {
+    EmptyLayer,
+    NoLayers,
+    InvalidDigit { position: usize, found: char },
+    DigitOutOfRange { position: usize, value: u32 },
+    IncompleteLayer { length: usize, layer_size: usize },
+    UnknownDigit(u32),
+    Io(std::io::ErrorKind),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct SifImage {
+    width: usize,
+    height: usize,
+    digits: Vec<u32>,
+    transparent: u32,
+}
+
+impl SifImage {
+    pub fn parse(data: &str, width: usize, height: usize) -> Result<Self, SifError> {
+        let digits = data
+            .trim()
+            .chars()
+            .enumerate()
+            .map(|(position, found)| {
+                found
+                    .to_digit(10)
+                    .ok_or(SifError::InvalidDigit { position, found })
+            })
+            .collect::<Result<Vec<u32>, SifError>>()?;
+        SifImage::new(width, height, digits)
+    }
+
+    pub fn from_layers(width: usize, height: usize, layers: &[Vec<u32>]) -> Result<Self, SifError> {
+        let layer_size = width * height;
+        if let Some(layer) = layers.iter().find(|layer| layer.len() != layer_size) {
+            return Err(SifError::IncompleteLayer {
+                length: layer.len(),
+                layer_size,
+            });
+        }
+        SifImage::new(width, height, layers.concat())
+    }
+
+    fn new(width: usize, height: usize, digits: Vec<u32>) -> Result<Self, SifError> {
+        let layer_size = width * height;
+        if layer_size == 0 {
+            return Err(SifError::EmptyLayer);
+        }
+        if digits.is_empty() {
+            return Err(SifError::NoLayers);
+        }
+        if let Some((position, value)) = digits.iter().enumerate().find(|(_, digit)| **digit > 9) {
+            return Err(SifError::DigitOutOfRange {
+                position,
+                value: *value,
+            });
+        }
+        if !digits.len().is_multiple_of(layer_size) {
+            return Err(SifError::IncompleteLayer {
+                length: digits.len(),
+                layer_size,
+            });
+        }
+        Ok(SifImage {
+            width,
+            height,
+            digits,
+            transparent: TRANSPARENT,
+        })
+    }
+
+    pub fn with_transparent(mut self, digit: u32) -> Result<Self, SifError> {
+        if digit > 9 {
+            return Err(SifError::UnknownDigit(digit));
+        }
+        self.transparent = digit;
+        Ok(self)
+    }
+
+    pub fn encode(&self) -> String {
+        self.digits
+            .iter()
+            .map(|digit| std::char::from_digit(*digit, 10).unwrap())
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn layer_size(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.digits.len() / self.layer_size()
+    }
+
+    pub fn layer(&self, n: usize) -> Option<&[u32]> {
+        self.layers().nth(n)
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = &[u32]> {
+        self.digits.chunks(self.layer_size())
+    }
+
+    pub fn pixel(&self, layer: usize, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.layer(layer).map(|digits| digits[x + y * self.width])
+    }
+
+    pub fn composite(&self) -> Vec<u32> {
+        let mut result = vec![self.transparent; self.layer_size()];
+        for layer in self.layers() {
+            for (target, source) in result.iter_mut().zip(layer) {
+                if *target == self.transparent {
+                    *target = *source;
                 }
             }
         }
+        result
+    }
+
+    pub fn render_rgb(&self, palette: &SifPalette, compositing: Compositing) -> Vec<Rgb> {
+        match compositing {
+            Compositing::FirstOpaque => self
+                .composite()
+                .into_iter()
+                .map(|digit| {
+                    if digit == self.transparent {
+                        palette.background
+                    } else {
+                        palette.color(digit).unwrap_or(palette.background)
+                    }
+                })
+                .collect(),
+            Compositing::Alpha => (0..self.layer_size())
+                .map(|index| self.blend(index, palette))
+                .collect(),
+        }
+    }
+
+    fn blend(&self, index: usize, palette: &SifPalette) -> Rgb {
+        let mut color = [0.0; 3];
+        let mut remaining = 1.0;
+        for layer in self.layers() {
+            let digit = layer[index];
+            if digit == self.transparent {
+                continue;
+            }
+            let (Some(digit_color), Some(alpha)) = (palette.color(digit), palette.alpha(digit))
+            else {
+                continue;
+            };
+            let alpha = f64::from(alpha) / 255.0;
+            for (channel, value) in color.iter_mut().zip(&digit_color) {
+                *channel += remaining * alpha * f64::from(*value);
+            }
+            remaining *= 1.0 - alpha;
+            if remaining <= 0.0 {
+                break;
+            }
+        }
+        let mut rgb = [0; 3];
+        for ((target, channel), background) in rgb.iter_mut().zip(&color).zip(&palette.background) {
+            *target = (channel + remaining * f64::from(*background)).round() as u8;
+        }
+        rgb
     }
-    result.into_iter().map(|d| d as u32).collect()
+
+    pub fn to_ansi(&self, palette: &SifPalette, compositing: Compositing) -> String {
+        let pixels = self.render_rgb(palette, compositing);
+        let mut text = String::new();
+        for row in pixels.chunks(self.width) {
+            for rgb in row {
+                text += &format!("\x1b[48;5;{}m \x1b[0m", ansi_256(*rgb));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn to_image(&self, palette: &SifPalette, compositing: Compositing) -> Image {
+        Image::from_rgb(
+            self.width,
+            self.height,
+            &self.render_rgb(palette, compositing),
+        )
+    }
+
+    pub fn layer_stats(&self) -> Vec<LayerStats> {
+        self.layers().map(LayerStats::new).collect()
+    }
+
+    pub fn fewest_zero_layer(&self) -> Option<usize> {
+        self.layer_stats()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, stats)| stats.count(0))
+            .map(|(n, _)| n)
+    }
+
+    pub fn checksum(&self) -> Option<usize> {
+        let layer = self.fewest_zero_layer()?;
+        Some(self.layer_stats()[layer].checksum())
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Compositing {
+    FirstOpaque,
+    Alpha,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct SifPalette {
+    colors: [Rgb; 10],
+    alphas: [u8; 10],
+    background: Rgb,
 }
 
-fn fewest_zero(data: &str, width: usize, height: usize) -> usize {
-    let digits = to_digits(data);
-    let num_layers = digits.len() / (width * height);
+impl SifPalette {
+    pub fn new(colors: [Rgb; 10]) -> Self {
+        SifPalette {
+            colors,
+            alphas: [255; 10],
+            background: [0, 0, 0],
+        }
+    }
+
+    pub fn classic() -> Self {
+        SifPalette::new([
+            [0, 0, 0],
+            [255, 255, 255],
+            [128, 128, 128],
+            [255, 0, 0],
+            [0, 255, 0],
+            [0, 0, 255],
+            [255, 255, 0],
+            [255, 0, 255],
+            [0, 255, 255],
+            [255, 128, 0],
+        ])
+    }
+
+    pub fn with_color(mut self, digit: u32, color: Rgb) -> Result<Self, SifError> {
+        *self
+            .colors
+            .get_mut(digit as usize)
+            .ok_or(SifError::UnknownDigit(digit))? = color;
+        Ok(self)
+    }
 
-    let mut quantity_of_zeroes = 10000000;
-    let mut less_zero_layer = 0;
-    for n in 0..num_layers {
-        let mut zeroes_in_current_layer = 0;
-        for i in 0..width {
-            for j in 0..height {
-                if digits[i + width * j + n * width * height] == 0 {
-                    zeroes_in_current_layer += 1;
+    pub fn with_alpha(mut self, digit: u32, alpha: u8) -> Result<Self, SifError> {
+        *self
+            .alphas
+            .get_mut(digit as usize)
+            .ok_or(SifError::UnknownDigit(digit))? = alpha;
+        Ok(self)
+    }
+
+    pub fn with_background(mut self, background: Rgb) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn color(&self, digit: u32) -> Option<Rgb> {
+        self.colors.get(digit as usize).copied()
+    }
+
+    pub fn alpha(&self, digit: u32) -> Option<u8> {
+        self.alphas.get(digit as usize).copied()
+    }
+}
+
+fn ansi_256(rgb: Rgb) -> u8 {
+    let [r, g, b] = rgb.map(|channel| ((u16::from(channel) * 5 + 127) / 255) as u8);
+    16 + 36 * r + 6 * g + b
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct LayerStats {
+    counts: [usize; 10],
+}
+
+impl LayerStats {
+    pub fn new(layer: &[u32]) -> Self {
+        let mut counts = [0; 10];
+        for digit in layer {
+            counts[*digit as usize] += 1;
+        }
+        LayerStats { counts }
+    }
+
+    pub fn count(&self, digit: u32) -> usize {
+        self.counts[digit as usize]
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.count(1) * self.count(2)
+    }
+}
+
+struct SifDecoder<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    width: usize,
+    height: usize,
+    transparent: u32,
+    stop_when_opaque: bool,
+    position: usize,
+    composite: Vec<u32>,
+    transparent_pixels: usize,
+    layer_stats: Vec<LayerStats>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct DecodedSif {
+    width: usize,
+    height: usize,
+    composite: Vec<u32>,
+    layer_stats: Vec<LayerStats>,
+    opaque_after: Option<usize>,
+    complete: bool,
+}
+
+impl<R: Read> SifDecoder<R> {
+    pub fn new(source: R, width: usize, height: usize) -> Self {
+        SifDecoder {
+            bytes: BufReader::new(source).bytes(),
+            width,
+            height,
+            transparent: TRANSPARENT,
+            stop_when_opaque: false,
+            position: 0,
+            composite: vec![TRANSPARENT; width * height],
+            transparent_pixels: width * height,
+            layer_stats: vec![],
+        }
+    }
+
+    pub fn with_transparent(mut self, digit: u32) -> Result<Self, SifError> {
+        if digit > 9 {
+            return Err(SifError::UnknownDigit(digit));
+        }
+        self.transparent = digit;
+        self.composite = vec![digit; self.width * self.height];
+        Ok(self)
+    }
+
+    pub fn stop_when_opaque(mut self) -> Self {
+        self.stop_when_opaque = true;
+        self
+    }
+
+    pub fn next_layer(&mut self) -> Result<Option<LayerStats>, SifError> {
+        let layer_size = self.width * self.height;
+        if layer_size == 0 {
+            return Err(SifError::EmptyLayer);
+        }
+        let mut counts = [0; 10];
+        let mut read = 0;
+        while read < layer_size {
+            let found = match self.bytes.next() {
+                None => break,
+                Some(Err(error)) => return Err(SifError::Io(error.kind())),
+                Some(Ok(byte)) if byte.is_ascii_whitespace() => continue,
+                Some(Ok(byte)) => byte as char,
+            };
+            let digit = found.to_digit(10).ok_or(SifError::InvalidDigit {
+                position: self.position,
+                found,
+            })?;
+            let target = &mut self.composite[read];
+            if *target == self.transparent && digit != self.transparent {
+                *target = digit;
+                self.transparent_pixels -= 1;
+            }
+            counts[digit as usize] += 1;
+            self.position += 1;
+            read += 1;
+        }
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < layer_size {
+            return Err(SifError::IncompleteLayer {
+                length: self.position,
+                layer_size,
+            });
+        }
+        let stats = LayerStats { counts };
+        self.layer_stats.push(stats);
+        Ok(Some(stats))
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.transparent_pixels == 0
+    }
+
+    pub fn decode(mut self) -> Result<DecodedSif, SifError> {
+        let mut opaque_after = None;
+        let mut complete = true;
+        while self.next_layer()?.is_some() {
+            if opaque_after.is_none() && self.is_opaque() {
+                opaque_after = Some(self.layer_stats.len() - 1);
+                if self.stop_when_opaque {
+                    complete = false;
+                    break;
                 }
             }
         }
-        if zeroes_in_current_layer < quantity_of_zeroes {
-            quantity_of_zeroes = zeroes_in_current_layer;
-            less_zero_layer = n;
+        if self.layer_stats.is_empty() {
+            return Err(SifError::NoLayers);
         }
+        Ok(DecodedSif {
+            width: self.width,
+            height: self.height,
+            composite: self.composite,
+            layer_stats: self.layer_stats,
+            opaque_after,
+            complete,
+        })
     }
-    less_zero_layer
 }
 
-fn decode(data: &str, width: usize, height: usize) -> usize {
-    let digits = to_digits(data);
+impl DecodedSif {
+    pub fn layers_read(&self) -> usize {
+        self.layer_stats.len()
+    }
+
+    pub fn checksum(&self) -> Option<usize> {
+        if !self.complete {
+            return None;
+        }
+        self.layer_stats
+            .iter()
+            .min_by_key(|stats| stats.count(0))
+            .map(LayerStats::checksum)
+    }
+}
+
+fn message_image(picture: &[u32], width: usize, height: usize) -> Image {
+    Image::new(
+        width,
+        height,
+        picture
+            .iter()
+            .map(|digit| match digit {
+                1 => 1,
+                _ => 0,
+            })
+            .collect(),
+    )
+    .with_palette(Palette::monochrome())
+}
+
+fn ocr_image(picture: &[u32], width: usize, height: usize) -> Option<String> {
+    let pixels: Vec<Vec<bool>> = (0..height)
+        .map(|j| (0..width).map(|i| picture[i + j * width] == 1).collect())
+        .collect();
+    ocr(&pixels)
+}
+
+const FONT: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+const GLYPH_HEIGHT: usize = 6;
+const GLYPH_PITCH: usize = 5;
+
+fn ocr(pixels: &[Vec<bool>]) -> Option<String> {
+    let first = pixels.iter().position(|row| row.contains(&true))?;
+    let last = pixels.iter().rposition(|row| row.contains(&true))?;
+    let rows = &pixels[first..=last];
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let column = |x: usize| -> Vec<bool> {
+        rows.iter()
+            .map(|row| row.get(x).copied().unwrap_or(false))
+            .collect()
+    };
+    let read = |start: usize| -> Option<String> {
+        let mut text = String::new();
+        for x in (start..width).step_by(GLYPH_PITCH) {
+            let glyph: Vec<Vec<bool>> = (x..x + GLYPH_PITCH).map(column).collect();
+            if glyph.iter().any(|column| column.contains(&true)) {
+                text.push(recognize(&glyph)?);
+            }
+        }
+        Some(text)
+    };
+    let left = (0..width).find(|x| column(*x).contains(&true))?;
+    (0..GLYPH_PITCH)
+        .filter_map(|shift| left.checked_sub(shift))
+        .find_map(read)
+}
 
-    let zero_layer = fewest_zero(data, width, height);
+fn recognize(columns: &[Vec<bool>]) -> Option<char> {
+    FONT.iter()
+        .find(|(_, rows)| glyph_columns(rows) == columns)
+        .map(|(letter, _)| *letter)
+}
 
-    decode_vec(&digits, width, height, zero_layer)
+fn glyph_columns(rows: &[&str; 6]) -> Vec<Vec<bool>> {
+    (0..GLYPH_PITCH)
+        .map(|x| {
+            rows.iter()
+                .map(|row| row.as_bytes().get(x) == Some(&b'#'))
+                .collect::<Vec<bool>>()
+        })
+        .collect()
 }
 
-fn decode_vec(digits: &[u32], width: usize, height: usize, zero_layer: usize) -> usize {
-    let mut num_ones = 0;
-    let mut num_twos = 0;
-    for i in 0..width {
-        for j in 0..height {
-            match digits[i + width * j + zero_layer * width * height] {
-                1 => num_ones += 1,
-                2 => num_twos += 1,
-                _ => {}
+#[allow(dead_code)]
+mod image {
+    use std::io;
+
+    pub type Rgb = [u8; 3];
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Palette {
+        colors: Vec<Rgb>,
+    }
+
+    impl Palette {
+        pub fn new(colors: Vec<Rgb>) -> Self {
+            if colors.is_empty() {
+                panic!("A palette needs at least one color.");
             }
+            Palette { colors }
+        }
+
+        pub fn monochrome() -> Self {
+            Palette::new(vec![[0, 0, 0], [255, 255, 255]])
+        }
+
+        pub fn len(&self) -> usize {
+            self.colors.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.colors.is_empty()
         }
+
+        pub fn color(&self, index: usize) -> Rgb {
+            *self
+                .colors
+                .get(index)
+                .unwrap_or_else(|| panic!("No color {} in a palette of {}", index, self.len()))
+        }
+
+        pub fn is_dark(&self, index: usize) -> bool {
+            let [r, g, b] = self.color(index);
+            299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b) < 128_000
+        }
+
+        pub fn hex(&self, index: usize) -> String {
+            let [r, g, b] = self.color(index);
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<usize>,
+        scale: usize,
+        palette: Palette,
     }
 
-    num_ones * num_twos
+    impl Image {
+        pub fn new(width: usize, height: usize, pixels: Vec<usize>) -> Self {
+            if pixels.len() != width * height {
+                panic!(
+                    "{} pixels can't form a {}x{} image.",
+                    pixels.len(),
+                    width,
+                    height
+                );
+            }
+            Image {
+                width,
+                height,
+                pixels,
+                scale: 1,
+                palette: Palette::monochrome(),
+            }
+        }
+
+        pub fn from_rows(rows: Vec<Vec<usize>>) -> Self {
+            let height = rows.len();
+            let width = rows.first().map_or(0, Vec::len);
+            if rows.iter().any(|row| row.len() != width) {
+                panic!("Rows of different length can't form an image.");
+            }
+            Image::new(width, height, rows.into_iter().flatten().collect())
+        }
+
+        pub fn from_bools(rows: &[Vec<bool>]) -> Self {
+            Image::from_rows(
+                rows.iter()
+                    .map(|row| row.iter().map(|set| *set as usize).collect())
+                    .collect(),
+            )
+        }
+
+        pub fn from_rgb(width: usize, height: usize, rgb: &[Rgb]) -> Self {
+            let mut colors: Vec<Rgb> = vec![];
+            let pixels = rgb
+                .iter()
+                .map(
+                    |color| match colors.iter().position(|known| known == color) {
+                        Some(index) => index,
+                        None => {
+                            colors.push(*color);
+                            colors.len() - 1
+                        }
+                    },
+                )
+                .collect();
+            Image::new(width, height, pixels).with_palette(Palette::new(colors))
+        }
+
+        pub fn with_scale(mut self, scale: usize) -> Self {
+            self.scale = scale.max(1);
+            self
+        }
+
+        pub fn with_palette(mut self, palette: Palette) -> Self {
+            self.palette = palette;
+            self
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.height
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> Option<usize> {
+            if x < self.width && y < self.height {
+                Some(self.pixels[x + y * self.width])
+            } else {
+                None
+            }
+        }
+
+        fn scaled_size(&self) -> (usize, usize) {
+            (self.width * self.scale, self.height * self.scale)
+        }
+
+        fn scaled(&self, px: usize, py: usize) -> usize {
+            self.pixels[px / self.scale + py / self.scale * self.width]
+        }
+
+        pub fn to_pbm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut pbm = format!("P4\n{} {}\n", width, height).into_bytes();
+            for py in 0..height {
+                let mut row = vec![0u8; width.div_ceil(8)];
+                for px in 0..width {
+                    if self.palette.is_dark(self.scaled(px, py)) {
+                        row[px / 8] |= 0x80 >> (px % 8);
+                    }
+                }
+                pbm.extend(row);
+            }
+            pbm
+        }
+
+        pub fn to_ppm(&self) -> Vec<u8> {
+            let (width, height) = self.scaled_size();
+            let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+            for py in 0..height {
+                for px in 0..width {
+                    ppm.extend_from_slice(&self.palette.color(self.scaled(px, py)));
+                }
+            }
+            ppm
+        }
+
+        pub fn to_svg(&self) -> String {
+            let (width, height) = self.scaled_size();
+            let mut svg = format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" shape-rendering=\"crispEdges\">\n",
+                width, height
+            );
+            svg += &format!(
+                "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                width,
+                height,
+                self.palette.hex(0)
+            );
+            for y in 0..self.height {
+                let mut x = 0;
+                while x < self.width {
+                    let index = self.pixels[x + y * self.width];
+                    let run = (x..self.width)
+                        .take_while(|&end| self.pixels[end + y * self.width] == index)
+                        .count();
+                    if index != 0 {
+                        svg += &format!(
+                            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                            x * self.scale,
+                            y * self.scale,
+                            run * self.scale,
+                            self.scale,
+                            self.palette.hex(index)
+                        );
+                    }
+                    x += run;
+                }
+            }
+            svg + "</svg>\n"
+        }
+
+        pub fn save(&self, path: &str) -> io::Result<()> {
+            let bytes = match path.rsplit('.').next() {
+                Some("pbm") => self.to_pbm(),
+                Some("ppm") => self.to_ppm(),
+                Some("svg") => self.to_svg().into_bytes(),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown image format: {}", path),
+                    ))
+                }
+            };
+            std::fs::write(path, bytes)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -98,20 +1034,144 @@ mod test {
         };
     }
 
+    fn sif(data: &str, width: usize, height: usize) -> SifImage {
+        SifImage::parse(data, width, height).unwrap()
+    }
+
+    fn stream(data: &str, width: usize, height: usize) -> Result<DecodedSif, SifError> {
+        SifDecoder::new(data.as_bytes(), width, height).decode()
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn glyph(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter()
+            .map(|row| row.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
     eq_tests! {
-        example_part1_1_fewest_zero: fewest_zero("123456789012", 3, 2) => 0;
-        example_part1_1_decode: decode("123456789012", 3, 2) => 1;
+        example_part1_1_fewest_zero: sif("123456789012", 3, 2).fewest_zero_layer() => Some(0);
+        example_part1_1_decode: sif("123456789012", 3, 2).checksum() => Some(1);
 
-        example_part1_2_fewest_zero: fewest_zero("123456789012000122", 3, 2) => 0;
-        example_part1_3_fewest_zero: fewest_zero("000122123456789012", 3, 2) => 1;
+        example_part1_2_fewest_zero: sif("123456789012000122", 3, 2).fewest_zero_layer() => Some(0);
+        example_part1_3_fewest_zero: sif("000122123456789012", 3, 2).fewest_zero_layer() => Some(1);
 
-        example_part1_2_decode: decode("123456789012000122", 3, 2) => 1;
-        example_part1_3_decode: decode("000122123456789012", 3, 2) => 1;
+        example_part1_2_decode: sif("123456789012000122", 3, 2).checksum() => Some(1);
+        example_part1_3_decode: sif("000122123456789012", 3, 2).checksum() => Some(1);
 
-        example_part1_4_decode: decode("111112223011111222201111122222", 10, 1) => 25;
+        example_part1_4_decode: sif("111112223011111222201111122222", 10, 1).checksum() => Some(25);
 
-        example_part2_1_render: render("0222112222120000", 2, 2) => vec![0, 1, 1, 0];
-        example_part2_1_render_and_decode: decode_vec(&render("0222112222120000", 2, 2), 2, 2, 0) => 0;
+        example_part2_1_render: sif("0222112222120000", 2, 2).composite() => vec![0, 1, 1, 0];
+        ocr_glyph_a: ocr(&glyph(&[".##.", "#..#", "#..#", "####", "#..#", "#..#"])) => Some("A".to_string());
+        ocr_with_margins: ocr(&glyph(&["......", ".###..", ".#..#.", ".###..", ".#..#.", ".#..#.", ".###..", "......"])) => Some("B".to_string());
+        ocr_narrow_glyph: ocr(&glyph(&["#.....###", "#......#.", "#......#.", "#......#.", "#......#.", "####..###"])) => Some("LI".to_string());
+        ocr_leading_blank_column: ocr(&glyph(&[".###.####", "..#.....#", "..#....#.", "..#...#..", "..#..#...", ".###.####"])) => Some("IZ".to_string());
+        ocr_full_width_y: ocr(&glyph(&["#...#####", "#...#...#", ".#.#...#.", "..#...#..", "..#..#...", "..#..####"])) => Some("YZ".to_string());
+        ocr_pitch_misaligned: ocr(&glyph(&["#...####", "#......#", "#.....#.", "#....#..", "#...#...", "########"])) => None;
+        ocr_unknown_glyph: ocr(&glyph(&["####", "####", "####", "####", "####", "####"])) => None;
+        ocr_wrong_height: ocr(&glyph(&["####", "#...", "###.", "#...", "####"])) => None;
+        ocr_empty: ocr(&glyph(&["....", "...."])) => None;
+        ocr_font_round_trip: FONT.iter().map(|(_, rows)| ocr(&glyph(rows)).unwrap()).collect::<String>() => FONT.iter().map(|(letter, _)| *letter).collect::<String>();
+        ocr_part2: ocr_image(&sif(PUZZLE_INPUT, 25, 6).composite(), 25, 6) => Some("AGUEB".to_string());
+        message_image_pixels: message_image(&[0, 1, 1, 0], 2, 2).get(1, 0) => Some(1);
+        message_image_out_of_bounds: message_image(&[0, 1, 1, 0], 2, 2).get(2, 0) => None;
+        message_image_pbm: message_image(&[0, 1, 1, 0], 2, 2).to_pbm() => b"P4\n2 2\n\x80\x40".to_vec();
+        message_image_pbm_scaled: message_image(&[0, 1, 1, 0], 2, 2).with_scale(2).to_pbm() => b"P4\n4 4\n\xc0\xc0\x30\x30".to_vec();
+        message_image_pbm_padding: message_image(&[1; 9], 9, 1).to_pbm() => b"P4\n9 1\n\x00\x00".to_vec();
+        message_image_pbm_inverted_palette: message_image(&[0, 1, 1, 0], 2, 2).with_palette(Palette::new(vec![[255, 255, 255], [0, 0, 0]])).to_pbm() => b"P4\n2 2\n\x40\x80".to_vec();
+        message_image_pbm_matches_ppm: message_image(&[0, 1], 2, 1).to_ppm()[11..].to_vec() => vec![0, 0, 0, 255, 255, 255];
+        message_image_transparent_is_background: message_image(&[2, 1, 7, 0], 2, 2).to_ppm()[11..].to_vec() => vec![0, 0, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0];
+        palette_is_dark: (0..4).map(|index| Palette::new(vec![[0, 0, 0], [255, 255, 255], [255, 0, 0], [0, 255, 0]]).is_dark(index)).collect::<Vec<_>>() => vec![true, false, true, false];
+        message_image_ppm: message_image(&[0, 1], 2, 1).to_ppm() => b"P6\n2 1\n255\n\x00\x00\x00\xff\xff\xff".to_vec();
+        message_image_ppm_palette: message_image(&[0, 1], 2, 1).with_palette(Palette::new(vec![[1, 2, 3], [4, 5, 6]])).to_ppm()[11..].to_vec() => vec![1, 2, 3, 4, 5, 6];
+        message_image_ppm_scaled_size: message_image(&sif(PUZZLE_INPUT, 25, 6).composite(), 25, 6).with_scale(4).to_ppm().len() => 14 + 100 * 24 * 3;
+        message_image_svg: message_image(&[1, 1, 0, 1], 4, 1).with_scale(3).to_svg().lines().skip(1).collect::<Vec<_>>() => vec![
+            "<rect width=\"12\" height=\"3\" fill=\"#000000\"/>",
+            "<rect x=\"0\" y=\"0\" width=\"6\" height=\"3\" fill=\"#ffffff\"/>",
+            "<rect x=\"9\" y=\"0\" width=\"3\" height=\"3\" fill=\"#ffffff\"/>",
+            "</svg>",
+        ];
+        image_from_bools: Image::from_bools(&[vec![true, false], vec![false, true]]) => Image::new(2, 2, vec![1, 0, 0, 1]);
+        image_palette_hex: Palette::new(vec![[255, 128, 0]]).hex(0) => "#ff8000";
+        image_save_unknown_format: message_image(&[0, 1], 2, 1).save("message.gif").is_err() => true;
+        sif_part1: sif(PUZZLE_INPUT, 25, 6).checksum() => Some(2760);
+        sif_dimensions: { let image = sif("123456789012000122", 3, 2); (image.width(), image.height(), image.layer_size(), image.layer_count()) } => (3, 2, 6, 3);
+        sif_layer: sif("123456789012", 3, 2).layer(1) => Some(&[7, 8, 9, 0, 1, 2][..]);
+        sif_layer_missing: sif("123456789012", 3, 2).layer(2) => None;
+        sif_pixel: sif("123456789012", 3, 2).pixel(1, 2, 1) => Some(2);
+        sif_pixel_outside: sif("123456789012", 3, 2).pixel(0, 3, 0) => None;
+        sif_trailing_newline: sif("0222112222120000\n", 2, 2).layer_count() => 4;
+        sif_fully_transparent: sif("2222", 2, 1).composite() => vec![2, 2];
+        sif_layer_stats: sif("000122123456789012", 3, 2).layer_stats()[0] => LayerStats { counts: [3, 1, 2, 0, 0, 0, 0, 0, 0, 0] };
+        sif_invalid_digit: SifImage::parse("0120a2", 3, 2) => Err(SifError::InvalidDigit { position: 4, found: 'a' });
+        sif_incomplete_layer: SifImage::parse("0120122", 3, 2) => Err(SifError::IncompleteLayer { length: 7, layer_size: 6 });
+        sif_empty_layer: SifImage::parse("0120", 0, 2) => Err(SifError::EmptyLayer);
+        sif_no_layers: SifImage::parse("", 3, 2) => Err(SifError::NoLayers);
+        sif_encode_round_trip: sif(PUZZLE_INPUT, 25, 6).encode() => PUZZLE_INPUT;
+        sif_from_layers: SifImage::from_layers(2, 2, &[vec![0, 2, 2, 2], vec![1, 1, 2, 2], vec![2, 2, 1, 2], vec![0, 0, 0, 0]]).map(|image| image.encode()) => Ok("0222112222120000".to_string());
+        sif_from_layers_wrong_size: SifImage::from_layers(2, 2, &[vec![0, 2, 2, 2], vec![1, 1, 2]]) => Err(SifError::IncompleteLayer { length: 3, layer_size: 4 });
+        sif_from_layers_out_of_range: SifImage::from_layers(2, 1, &[vec![0, 2], vec![10, 1]]) => Err(SifError::DigitOutOfRange { position: 2, value: 10 });
+        parse_arg_default: parse_arg(&args(&["day8", "export"]), 3, 10) => Ok(10);
+        parse_arg_invalid: parse_arg::<usize>(&args(&["day8", "export", "out.svg", "x"]), 3, 10) => Err(UsageError::InvalidArgument { position: 3, found: "x".to_string() });
+        required_arg_missing: required_arg::<usize>(&args(&["day8", "pixel", "1"]), 3) => Err(UsageError::MissingArgument { position: 3 });
+        bounded_arg_in_range: bounded_arg(&args(&["day8", "pixel", "24", "5"]), 3, 6) => Ok(5);
+        bounded_arg_out_of_range: bounded_arg(&args(&["day8", "pixel", "25", "0"]), 2, 25) => Err(UsageError::InvalidArgument { position: 2, found: "25".to_string() });
+        sif_flatten_round_trip: SifImage::from_layers(2, 2, &[sif("0222112222120000", 2, 2).composite()]).map(|image| (image.layer_count(), image.encode())) => Ok((1, "0110".to_string()));
+        sif_transparent_digit: sif("0222112222120000", 2, 2).with_transparent(0).map(|image| image.composite()) => Ok(vec![1, 2, 2, 2]);
+        sif_transparent_digit_fallthrough: sif("0221122011110000", 2, 2).with_transparent(1).map(|image| image.composite()) => Ok(vec![0, 2, 2, 0]);
+        sif_transparent_digit_out_of_range: sif("0222", 2, 2).with_transparent(10) => Err(SifError::UnknownDigit(10));
+        sif_render_classic: sif("0222112222120000", 2, 2).render_rgb(&SifPalette::classic(), Compositing::FirstOpaque) => vec![[0, 0, 0], [255, 255, 255], [255, 255, 255], [0, 0, 0]];
+        sif_render_alpha_opaque_matches_first_opaque: sif(PUZZLE_INPUT, 25, 6).render_rgb(&SifPalette::classic(), Compositing::Alpha) => sif(PUZZLE_INPUT, 25, 6).render_rgb(&SifPalette::classic(), Compositing::FirstOpaque);
+        sif_render_background: sif("22", 2, 1).render_rgb(&SifPalette::classic().with_background([1, 2, 3]), Compositing::Alpha) => vec![[1, 2, 3], [1, 2, 3]];
+        sif_render_alpha_blend: sif("31", 1, 1).render_rgb(&SifPalette::classic().with_alpha(3, 128).unwrap(), Compositing::Alpha) => vec![[255, 127, 127]];
+        sif_render_alpha_over_background: sif("3", 1, 1).render_rgb(&SifPalette::classic().with_alpha(3, 51).unwrap().with_background([0, 0, 255]), Compositing::Alpha) => vec![[51, 0, 204]];
+        sif_render_first_opaque_ignores_alpha: sif("31", 1, 1).render_rgb(&SifPalette::classic().with_alpha(3, 128).unwrap(), Compositing::FirstOpaque) => vec![[255, 0, 0]];
+        sif_render_custom_color: sif("9", 1, 1).render_rgb(&SifPalette::classic().with_color(9, [10, 20, 30]).unwrap(), Compositing::FirstOpaque) => vec![[10, 20, 30]];
+        sif_palette_out_of_range: (SifPalette::classic().color(10), SifPalette::classic().alpha(10), SifPalette::classic().with_color(10, [0, 0, 0]), SifPalette::classic().with_alpha(12, 0)) => (None, None, Err(SifError::UnknownDigit(10)), Err(SifError::UnknownDigit(12)));
+        palette_arg_color: parse_palette_arg(SifPalette::classic(), 4, ("3", "102030")) => SifPalette::classic().with_color(3, [16, 32, 48]).map_err(UsageError::Sif);
+        palette_arg_alpha: parse_palette_arg(SifPalette::classic(), 4, ("3", "10203080")).map(|palette| palette.alpha(3)) => Ok(Some(128));
+        palette_arg_background: parse_palette_arg(SifPalette::classic(), 5, ("bg", "0000ff")) => Ok(SifPalette::classic().with_background([0, 0, 255]));
+        palette_arg_digit_out_of_range: parse_palette_arg(SifPalette::classic(), 4, ("12", "ffffff")) => Err(UsageError::Sif(SifError::UnknownDigit(12)));
+        palette_arg_bad_hex: parse_palette_arg(SifPalette::classic(), 4, ("3", "+12345")) => Err(UsageError::InvalidArgument { position: 4, found: "3=+12345".to_string() });
+        palette_arg_background_alpha: parse_palette_arg(SifPalette::classic(), 4, ("bg", "000000ff")) => Err(UsageError::InvalidArgument { position: 4, found: "bg=000000ff".to_string() });
+        sif_ansi: sif("0222112222120000", 2, 2).to_ansi(&SifPalette::classic(), Compositing::FirstOpaque) => "\x1b[48;5;16m \x1b[0m\x1b[48;5;231m \x1b[0m\n\x1b[48;5;231m \x1b[0m\x1b[48;5;16m \x1b[0m\n";
+        sif_ansi_256_colors: [[255, 0, 0], [0, 255, 0], [0, 0, 255], [128, 128, 128]].iter().map(|rgb| ansi_256(*rgb)).collect::<Vec<_>>() => vec![196, 46, 21, 145];
+        sif_to_image_ppm: sif("3450", 2, 2).to_image(&SifPalette::classic(), Compositing::FirstOpaque).to_ppm()[11..].to_vec() => vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0];
+        sif_to_image_palette: sif("3443", 2, 2).to_image(&SifPalette::classic(), Compositing::FirstOpaque) => Image::new(2, 2, vec![0, 1, 1, 0]).with_palette(Palette::new(vec![[255, 0, 0], [0, 255, 0]]));
+        stream_example_composite: stream("0222112222120000", 2, 2).map(|decoded| decoded.composite) => Ok(vec![0, 1, 1, 0]);
+        stream_example_checksum: stream("123456789012", 3, 2).map(|decoded| decoded.checksum()) => Ok(Some(1));
+        stream_example_3_checksum: stream("000122123456789012", 3, 2).map(|decoded| decoded.checksum()) => Ok(Some(1));
+        stream_matches_sif_image: { let decoded = stream(PUZZLE_INPUT, 25, 6).unwrap(); (decoded.checksum(), decoded.composite, decoded.layer_stats) } => { let image = sif(PUZZLE_INPUT, 25, 6); (image.checksum(), image.composite(), image.layer_stats()) };
+        stream_opaque_after: stream("0222112222120000", 2, 2).map(|decoded| (decoded.opaque_after, decoded.layers_read())) => Ok((Some(3), 4));
+        stream_stop_when_opaque: SifDecoder::new("02221122011022220000".as_bytes(), 2, 2).stop_when_opaque().decode().map(|decoded| (decoded.layers_read(), decoded.composite)) => Ok((3, vec![0, 1, 1, 0]));
+        stream_stop_when_opaque_skips_bad_tail: SifDecoder::new("01x".as_bytes(), 2, 1).stop_when_opaque().decode().map(|decoded| decoded.layers_read()) => Ok(1);
+        stream_stop_when_opaque_has_no_checksum: SifDecoder::new("00111122".as_bytes(), 2, 1).stop_when_opaque().decode().map(|decoded| (decoded.layers_read(), decoded.checksum())) => Ok((1, None));
+        stream_full_decode_checksum: stream("00111122", 2, 1).map(|decoded| (decoded.opaque_after, decoded.checksum())) => Ok((Some(0), Some(0)));
+        stream_never_opaque: stream("2222", 2, 1).map(|decoded| (decoded.composite, decoded.opaque_after)) => Ok((vec![2, 2], None));
+        stream_transparent_digit: SifDecoder::new("0222112222120000".as_bytes(), 2, 2).with_transparent(0).and_then(SifDecoder::decode).map(|decoded| decoded.composite) => Ok(vec![1, 2, 2, 2]);
+        stream_transparent_out_of_range: SifDecoder::new("0222".as_bytes(), 2, 2).with_transparent(10).err() => Some(SifError::UnknownDigit(10));
+        stream_configure_decoder: configure_decoder(SifDecoder::new("02221122011022220000".as_bytes(), 2, 2), 2, true).map(|decoder| decoder.decode().map(|decoded| decoded.layers_read())) => Ok(Ok(3));
+        stream_configure_decoder_transparent: configure_decoder(SifDecoder::new("0222112222120000".as_bytes(), 2, 2), 0, false).map(|decoder| decoder.decode().map(|decoded| decoded.composite)) => Ok(Ok(vec![1, 2, 2, 2]));
+        stream_configure_decoder_out_of_range: configure_decoder(SifDecoder::new("0222".as_bytes(), 2, 2), 11, false).err() => Some(UsageError::Sif(SifError::UnknownDigit(11)));
+        stream_whitespace: stream("02\n22\n11\n22\n", 2, 2).map(|decoded| decoded.layers_read()) => Ok(2);
+        stream_invalid_digit: stream("0120a2", 3, 2) => Err(SifError::InvalidDigit { position: 4, found: 'a' });
+        stream_incomplete_layer: stream("0120122", 3, 2) => Err(SifError::IncompleteLayer { length: 7, layer_size: 6 });
+        stream_no_layers: stream("\n", 3, 2) => Err(SifError::NoLayers);
+        stream_empty_layer: stream("0120", 0, 2) => Err(SifError::EmptyLayer);
+        stream_io_error: SifDecoder::new(FailingReader, 2, 2).decode() => Err(SifError::Io(std::io::ErrorKind::BrokenPipe));
+        stream_next_layer: { let mut decoder = SifDecoder::new("123456789012".as_bytes(), 3, 2); (decoder.next_layer(), decoder.next_layer().map(|stats| stats.map(|stats| stats.checksum())), decoder.next_layer()) } => (Ok(Some(LayerStats { counts: [0, 1, 1, 1, 1, 1, 1, 0, 0, 0] })), Ok(Some(1)), Ok(None));
+        example_part2_1_render_and_decode: LayerStats::new(&sif("0222112222120000", 2, 2).composite()).checksum() => 0;
     }
 }
 
@@ -1,173 +1,1200 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
 
 fn main() {
-    let outputs = Computer::new(PUZZLE_INPUT.to_vec()).with_input(1).run();
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "repl" {
+        let program = match args.get(2) {
+            Some(path) => read_program(path).unwrap(),
+            None => PUZZLE_INPUT.to_vec(),
+        };
+        let stdin = std::io::stdin();
+        repl(program, stdin.lock(), std::io::stdout()).unwrap();
+        return;
+    }
+    if args.len() > 3 && args[1] == "run" {
+        let inputs = &args[4..];
+        let result = match args[2].as_str() {
+            "i64" => run_file::<i64>(&args[3], inputs),
+            "i128" => run_file::<i128>(&args[3], inputs),
+            "bignum" => run_file::<BigInt>(&args[3], inputs),
+            word => Err(format!("Unknown word type: {}", word)),
+        };
+        match result {
+            Ok(report) => println!("{}", report),
+            Err(error) => println!("error: {}", error),
+        }
+        return;
+    }
+    if args.len() > 1 && args[1] == "corpus" {
+        let dir = args.get(2).map_or(CORPUS_DIR, String::as_str);
+        let (passed, failures) = run_corpus_dir(dir).unwrap();
+        for failure in failures.iter() {
+            println!("FAIL {}", failure);
+        }
+        println!("corpus: {} passed, {} failed", passed, failures.len());
+        return;
+    }
+    if args.len() > 1 && args[1] == "memory-map" {
+        let prefix = args.get(2).map_or("memory_map", String::as_str);
+        write_memory_map(prefix).unwrap();
+        return;
+    }
+
+    let outputs = Computer::new(PUZZLE_INPUT.to_vec())
+        .with_input(1)
+        .run()
+        .unwrap();
     println!("1. outputs: {:?}", outputs);
 
-    let outputs = Computer::new(PUZZLE_INPUT.to_vec()).with_input(2).run();
+    let outputs = Computer::new(PUZZLE_INPUT.to_vec())
+        .with_input(2)
+        .run()
+        .unwrap();
     println!("2. outputs: {:?}", outputs);
 }
 
-type Instructions = Vec<i64>;
+fn write_memory_map(prefix: &str) -> std::io::Result<()> {
+    let mut computer = Computer::new(PUZZLE_INPUT.to_vec())
+        .with_input(1)
+        .with_trace();
+    computer.run().unwrap();
+    let map = computer.memory_map();
+    std::fs::write(format!("{}.txt", prefix), map.to_text(MEMORY_MAP_COLUMNS))?;
+    std::fs::write(format!("{}.ppm", prefix), map.to_ppm(MEMORY_MAP_COLUMNS))?;
+    std::fs::write(format!("{}.html", prefix), map.to_html(MEMORY_MAP_COLUMNS))?;
+    println!("Memory map written to {}.{{txt,ppm,html}}", prefix);
+    Ok(())
+}
+
+const MEMORY_MAP_COLUMNS: usize = 64;
+
+fn run_file<W: Word>(path: &str, inputs: &[String]) -> Result<String, String> {
+    let mut computer = Computer::new(read_program::<W>(path)?);
+    for input in inputs {
+        computer.add_input(parse_value(input)?);
+    }
+    let (outputs, end) = computer.drain_outputs();
+    Ok(format!("outputs: {}\n{:?}", join(outputs.iter()), end))
+}
+
+trait Word: Clone + PartialEq + PartialOrd + Debug + Display + FromStr {
+    fn from_i64(n: i64) -> Self;
+    fn to_i64(&self) -> Option<i64>;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_word {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Word for $t {
+                fn from_i64(n: i64) -> Self {
+                    n.into()
+                }
+                fn to_i64(&self) -> Option<i64> {
+                    i64::try_from(*self).ok()
+                }
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_word!(i64, i128);
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        let negative = negative && !magnitude.is_empty();
+        BigInt {
+            negative,
+            magnitude,
+        }
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = carry
+            + u64::from(a.get(i).copied().unwrap_or(0))
+            + u64::from(b.get(i).copied().unwrap_or(0));
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter().enumerate() {
+        let mut difference = i64::from(*limb) - i64::from(b.get(i).copied().unwrap_or(0)) - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    result
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = u64::from(*x) * u64::from(*y) + u64::from(result[i + j]) + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    result
+}
+
+fn div_rem_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = 0u64;
+    for (i, limb) in a.iter().enumerate().rev() {
+        let current = (remainder << 32) | u64::from(*limb);
+        quotient[i] = (current / u64::from(divisor)) as u32;
+        remainder = current % u64::from(divisor);
+    }
+    (quotient, remainder as u32)
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        })
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.magnitude.is_empty() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, chunk) = div_rem_small(&magnitude, 1_000_000_000);
+            chunks.push(chunk);
+            magnitude = BigInt::new(false, quotient).magnitude;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return Err(format!("Invalid number: {}", text));
+        }
+        let magnitude = digits.bytes().fold(vec![], |magnitude, digit| {
+            let ten = mul_magnitudes(&magnitude, &[10]);
+            add_magnitudes(&ten, &[u32::from(digit - b'0')])
+        });
+        Ok(BigInt::new(negative, magnitude))
+    }
+}
 
-struct Computer {
-    instructions: Instructions,
-    input: VecDeque<i64>,
-    memory: HashMap<i64, i64>,
+impl Word for BigInt {
+    fn from_i64(n: i64) -> Self {
+        let magnitude = n.unsigned_abs();
+        BigInt::new(n < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+    fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, limb| (acc << 32) | u64::from(*limb));
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(if self.negative == rhs.negative {
+            BigInt::new(
+                self.negative,
+                add_magnitudes(&self.magnitude, &rhs.magnitude),
+            )
+        } else if compare_magnitudes(&self.magnitude, &rhs.magnitude) == Ordering::Less {
+            BigInt::new(
+                rhs.negative,
+                sub_magnitudes(&rhs.magnitude, &self.magnitude),
+            )
+        } else {
+            BigInt::new(
+                self.negative,
+                sub_magnitudes(&self.magnitude, &rhs.magnitude),
+            )
+        })
+    }
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Some(BigInt::new(
+            self.negative != rhs.negative,
+            mul_magnitudes(&self.magnitude, &rhs.magnitude),
+        ))
+    }
+}
+
+type Instructions<W = i64> = Vec<W>;
+
+struct Computer<W: Word = i64> {
+    instructions: Instructions<W>,
+    input: VecDeque<W>,
+    memory: HashMap<i64, W>,
     ptr: i64,
     relative_base: i64,
+    trace: Option<HashMap<i64, CellAccess>>,
 }
 
-enum ComputerExecution {
-    Yield(i64),
+#[derive(Debug, PartialEq)]
+enum ComputerExecution<W> {
+    Yield(W),
     Halt,
+    NeedInput,
+    Error(ComputerError),
 }
 
-impl Computer {
-    pub fn new(instructions: Instructions) -> Self {
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum ComputerError {
+    Overflow { ptr: i64, opcode: u16 },
+    MissingInput { ptr: i64 },
+    UnknownOpcode { ptr: i64, opcode: u16 },
+    UnknownMode { ptr: i64, mode: u16 },
+    NegativeAddress { ptr: i64, address: i64 },
+    ValueOutOfRange { ptr: i64 },
+}
+
+impl<W: Word> Computer<W> {
+    pub fn new(instructions: Instructions<W>) -> Self {
         Computer {
             instructions,
             memory: HashMap::new(),
             input: VecDeque::new(),
             ptr: 0,
             relative_base: 0,
+            trace: None,
         }
     }
 
-    pub fn with_input(mut self, input: i64) -> Self {
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(HashMap::new());
+        self
+    }
+
+    pub fn with_input(mut self, input: W) -> Self {
         self.add_input(input);
         self
     }
 
-    pub fn add_input(&mut self, input: i64) {
+    pub fn add_input(&mut self, input: W) {
         self.input.push_back(input);
     }
 
-    pub fn run(&mut self) -> Vec<i64> {
+    pub fn run(&mut self) -> Result<Vec<W>, ComputerError> {
+        let (outputs, end) = self.drain_outputs();
+        match end {
+            ComputerExecution::Halt => Ok(outputs),
+            ComputerExecution::NeedInput => Err(ComputerError::MissingInput { ptr: self.ptr }),
+            ComputerExecution::Error(error) => Err(error),
+            ComputerExecution::Yield(_) => unreachable!(),
+        }
+    }
+
+    pub fn drain_outputs(&mut self) -> (Vec<W>, ComputerExecution<W>) {
         let mut outputs = vec![];
-        while let ComputerExecution::Yield(output) = self.next_output() {
-            outputs.push(output);
+        loop {
+            match self.next_output() {
+                ComputerExecution::Yield(output) => outputs.push(output),
+                end => return (outputs, end),
+            }
         }
-        outputs
     }
 
-    pub fn next_output(&mut self) -> ComputerExecution {
+    pub fn next_output(&mut self) -> ComputerExecution<W> {
+        self.execute().unwrap_or_else(ComputerExecution::Error)
+    }
+
+    fn execute(&mut self) -> Result<ComputerExecution<W>, ComputerError> {
         loop {
-            let op = self.opcode();
+            let op = self.opcode()?;
+            if op.de == 3 && self.input.is_empty() {
+                return Ok(ComputerExecution::NeedInput);
+            }
+            let last = self
+                .ptr
+                .checked_add(instruction_length(op.de) - 1)
+                .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })?;
+            for address in self.ptr..=last {
+                self.mark(address, |cell| cell.executions += 1);
+            }
             match op.de {
                 1 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    self.set_third(op, first + second);
-                    self.ptr += 4;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    match first.checked_add(second) {
+                        Some(result) => self.set_third(op, result)?,
+                        None => return Err(self.overflow(op)),
+                    }
+                    self.advance(4)?;
                 }
                 2 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    self.set_third(op, first * second);
-                    self.ptr += 4;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    match first.checked_mul(second) {
+                        Some(result) => self.set_third(op, result)?,
+                        None => return Err(self.overflow(op)),
+                    }
+                    self.advance(4)?;
                 }
                 3 => {
-                    if let Some(input) = self.input.pop_front() {
-                        self.set_first(op, input);
-                    } else {
-                        panic!("Missing input!");
-                    }
-                    self.ptr += 2;
+                    let input = self.input.pop_front().expect("Missing input!");
+                    self.set_first(op, input)?;
+                    self.advance(2)?;
                 }
                 4 => {
-                    let first = self.get_first(op);
-                    self.ptr += 2;
-                    return ComputerExecution::Yield(first);
+                    let first = self.get_first(op)?;
+                    self.advance(2)?;
+                    return Ok(ComputerExecution::Yield(first));
                 }
                 5 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    if first != 0 {
-                        if second < 0 {
-                            panic!("Second can't be 0 here: {}", second);
-                        }
-                        self.ptr = second;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    if first != W::from_i64(0) {
+                        self.ptr = self.jump_target(second)?;
                     } else {
-                        self.ptr += 3;
+                        self.advance(3)?;
                     }
                 }
                 6 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    if first == 0 {
-                        if second < 0 {
-                            panic!("Second can't be less than 0 here: {}", second);
-                        }
-                        self.ptr = second;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    if first == W::from_i64(0) {
+                        self.ptr = self.jump_target(second)?;
                     } else {
-                        self.ptr += 3;
+                        self.advance(3)?;
                     }
                 }
                 7 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    self.set_third(op, if first < second { 1 } else { 0 });
-                    self.ptr += 4;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    self.set_third(op, W::from_i64(if first < second { 1 } else { 0 }))?;
+                    self.advance(4)?;
                 }
                 8 => {
-                    let first = self.get_first(op);
-                    let second = self.get_second(op);
-                    self.set_third(op, if first == second { 1 } else { 0 });
-                    self.ptr += 4;
+                    let first = self.get_first(op)?;
+                    let second = self.get_second(op)?;
+                    self.set_third(op, W::from_i64(if first == second { 1 } else { 0 }))?;
+                    self.advance(4)?;
                 }
                 9 => {
-                    let first = self.get_first(op);
-                    self.relative_base += first;
-                    self.ptr += 2;
+                    let first = self.get_first(op)?;
+                    self.relative_base = self
+                        .to_address(first)?
+                        .checked_add(self.relative_base)
+                        .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })?;
+                    self.advance(2)?;
+                }
+                99 => return Ok(ComputerExecution::Halt),
+                _ => {
+                    return Err(ComputerError::UnknownOpcode {
+                        ptr: self.ptr,
+                        opcode: op.de,
+                    })
                 }
-                99 => return ComputerExecution::Halt,
-                _ => panic!("Opcode not implemented: {}", op.de),
             }
         }
     }
 
-    pub fn get_first(&mut self, op: Opcode) -> i64 {
-        *self.access(op.c, 1)
+    fn advance(&mut self, length: i64) -> Result<(), ComputerError> {
+        self.ptr = self
+            .ptr
+            .checked_add(length)
+            .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })?;
+        Ok(())
+    }
+
+    fn overflow(&self, op: Opcode) -> ComputerError {
+        ComputerError::Overflow {
+            ptr: self.ptr,
+            opcode: op.de,
+        }
+    }
+
+    fn jump_target(&self, value: W) -> Result<i64, ComputerError> {
+        let address = self.to_address(value)?;
+        if address < 0 {
+            return Err(ComputerError::NegativeAddress {
+                ptr: self.ptr,
+                address,
+            });
+        }
+        Ok(address)
     }
-    pub fn get_second(&mut self, op: Opcode) -> i64 {
-        *self.access(op.b, 2)
+
+    pub fn get_first(&mut self, op: Opcode) -> Result<W, ComputerError> {
+        self.read(op.c, 1)
     }
-    pub fn set_third(&mut self, op: Opcode, value: i64) {
-        *self.access(op.a, 3) = value;
+    pub fn get_second(&mut self, op: Opcode) -> Result<W, ComputerError> {
+        self.read(op.b, 2)
     }
-    pub fn set_first(&mut self, op: Opcode, value: i64) {
-        *self.access(op.c, 1) = value;
+    pub fn set_third(&mut self, op: Opcode, value: W) -> Result<(), ComputerError> {
+        self.write(op.a, 3, value)
+    }
+    pub fn set_first(&mut self, op: Opcode, value: W) -> Result<(), ComputerError> {
+        self.write(op.c, 1, value)
     }
 
-    pub fn access(&mut self, mode: u16, offset: i64) -> &mut i64 {
-        match mode {
-            0 => {
-                let ptr = *self.instruction(self.ptr + offset);
-                self.instruction(ptr)
-            }
-            1 => self.instruction(self.ptr + offset),
-            2 => {
-                let ptr = *self.instruction(self.ptr + offset);
-                self.instruction(ptr + self.relative_base)
+    fn read(&mut self, mode: u16, offset: i64) -> Result<W, ComputerError> {
+        let address = self.address(mode, offset)?;
+        if mode != 1 {
+            self.mark(address, |cell| cell.reads += 1);
+        }
+        Ok(self.instruction(address).clone())
+    }
+
+    fn write(&mut self, mode: u16, offset: i64, value: W) -> Result<(), ComputerError> {
+        let address = self.address(mode, offset)?;
+        self.mark(address, |cell| cell.writes += 1);
+        *self.instruction(address) = value;
+        Ok(())
+    }
+
+    fn mark(&mut self, address: i64, update: impl Fn(&mut CellAccess)) {
+        if let Some(trace) = self.trace.as_mut() {
+            update(trace.entry(address).or_default());
+        }
+    }
+
+    fn address(&mut self, mode: u16, offset: i64) -> Result<i64, ComputerError> {
+        let position = self
+            .ptr
+            .checked_add(offset)
+            .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })?;
+        let parameter = self.instruction(position).clone();
+        let address = match mode {
+            0 => self.to_address(parameter)?,
+            1 => position,
+            2 => self
+                .to_address(parameter)?
+                .checked_add(self.relative_base)
+                .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })?,
+            _ => {
+                return Err(ComputerError::UnknownMode {
+                    ptr: self.ptr,
+                    mode,
+                })
             }
-            _ => panic!("Mode not implemented: {}", mode),
+        };
+        if address < 0 {
+            return Err(ComputerError::NegativeAddress {
+                ptr: self.ptr,
+                address,
+            });
         }
+        Ok(address)
     }
 
-    pub fn instruction(&mut self, position: i64) -> &mut i64 {
+    fn to_address(&self, value: W) -> Result<i64, ComputerError> {
+        value
+            .to_i64()
+            .ok_or(ComputerError::ValueOutOfRange { ptr: self.ptr })
+    }
+
+    pub fn instruction(&mut self, position: i64) -> &mut W {
         if position >= self.instructions.len() as i64 {
-            if !self.memory.contains_key(&position) {
-                self.memory.insert(position, 0);
-            }
-            self.memory.get_mut(&position).expect("Not initialized.")
+            self.memory
+                .entry(position)
+                .or_insert_with(|| W::from_i64(0))
         } else {
             &mut self.instructions[position as usize]
         }
     }
 
-    fn opcode(&mut self) -> Opcode {
-        to_opcode(*self.instruction(self.ptr))
+    pub fn peek(&self, position: i64) -> Option<W> {
+        if position < 0 {
+            None
+        } else if position >= self.instructions.len() as i64 {
+            Some(
+                self.memory
+                    .get(&position)
+                    .cloned()
+                    .unwrap_or_else(|| W::from_i64(0)),
+            )
+        } else {
+            Some(self.instructions[position as usize].clone())
+        }
+    }
+
+    fn opcode(&mut self) -> Result<Opcode, ComputerError> {
+        let value = self.instruction(self.ptr).clone();
+        Ok(to_opcode(self.to_address(value)?))
+    }
+}
+
+impl<W: Word> Computer<W> {
+    pub fn snapshot(&self) -> String {
+        let mut memory: Vec<_> = self.memory.iter().collect();
+        memory.sort_by_key(|(address, _)| **address);
+        format!(
+            "ptr {}\nrelative_base {}\ninput {}\ninstructions {}\nmemory {}\n",
+            self.ptr,
+            self.relative_base,
+            join(self.input.iter()),
+            join(self.instructions.iter()),
+            join(
+                memory
+                    .into_iter()
+                    .map(|(address, value)| format!("{}={}", address, value))
+            ),
+        )
+    }
+
+    pub fn from_snapshot(snapshot: &str) -> Result<Self, String> {
+        let mut computer = Computer::new(vec![]);
+        for line in snapshot.lines().filter(|line| !line.trim().is_empty()) {
+            let mut parts = line.trim().splitn(2, ' ');
+            let key = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("").trim();
+            match key {
+                "ptr" => computer.ptr = parse_value(value)?,
+                "relative_base" => computer.relative_base = parse_value(value)?,
+                "input" => computer.input = parse_program(value)?.into_iter().collect(),
+                "instructions" => computer.instructions = parse_program(value)?,
+                "memory" => computer.memory.extend(parse_memory(value)?),
+                _ => return Err(format!("Unknown snapshot entry: {}", key)),
+            }
+        }
+        Ok(computer)
+    }
+}
+
+fn join<T: Display>(values: impl Iterator<Item = T>) -> String {
+    values
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn parse_value<T: FromStr>(text: &str) -> Result<T, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("Invalid number: {}", text.trim()))
+}
+
+fn parse_program<W: Word>(text: &str) -> Result<Instructions<W>, String> {
+    text.split(',')
+        .filter(|value| !value.trim().is_empty())
+        .map(parse_value)
+        .collect()
+}
+
+fn parse_memory<W: Word>(text: &str) -> Result<Vec<(i64, W)>, String> {
+    text.split(',')
+        .filter(|cell| !cell.trim().is_empty())
+        .map(|cell| {
+            let mut cell = cell.splitn(2, '=');
+            let address = parse_value(cell.next().unwrap_or(""))?;
+            let value = parse_value(cell.next().unwrap_or(""))?;
+            Ok((address, value))
+        })
+        .collect()
+}
+
+fn read_program<W: Word>(path: &str) -> Result<Instructions<W>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_program(&text)
+}
+
+struct Repl {
+    program: Instructions,
+    computer: Computer,
+}
+
+const REPL_HELP: &str = "commands:
+  input <n>...           queue input values
+  next                   run until next output, halt or need of input
+  run                    run until halt or need of input
+  get <address> [count]  print memory cells
+  set <address> <value>  write a memory cell
+  state                  print ptr, relative base and pending input
+  reset                  restart the loaded program
+  load <file>            load a program from a file
+  save <file>            write a snapshot of the computer
+  restore <file>         load a snapshot of the computer
+  quit                   leave";
+
+const REPL_MAX_CELLS: i64 = 1024;
+
+fn parse_address(text: &str) -> Result<i64, String> {
+    let address = parse_value(text)?;
+    if address < 0 {
+        return Err(format!("Invalid address: {}", address));
+    }
+    Ok(address)
+}
+
+impl Repl {
+    pub fn new(program: Instructions) -> Self {
+        let computer = Computer::new(program.clone());
+        Repl { program, computer }
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<Option<String>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let args = if words.is_empty() {
+            &words[..]
+        } else {
+            &words[1..]
+        };
+        let message = match words.first().copied().unwrap_or("") {
+            "" => String::new(),
+            "help" | "h" => REPL_HELP.to_string(),
+            "input" | "i" => {
+                if args.is_empty() {
+                    return Err("Usage: input <n>...".to_string());
+                }
+                for arg in args {
+                    self.computer.add_input(parse_value(arg)?);
+                }
+                format!("input: {:?}", self.computer.input)
+            }
+            "next" | "n" => match self.computer.next_output() {
+                ComputerExecution::Yield(output) => format!("output: {}", output),
+                ComputerExecution::Halt => "halt".to_string(),
+                ComputerExecution::NeedInput => "need input".to_string(),
+                ComputerExecution::Error(error) => return Err(format!("{:?}", error)),
+            },
+            "run" | "r" => {
+                let (outputs, end) = self.computer.drain_outputs();
+                let end = match end {
+                    ComputerExecution::Halt => "halt",
+                    ComputerExecution::NeedInput => "need input",
+                    ComputerExecution::Error(error) => {
+                        return Err(format!("{:?} after outputs {:?}", error, outputs))
+                    }
+                    ComputerExecution::Yield(_) => unreachable!(),
+                };
+                format!("outputs: {:?}\n{}", outputs, end)
+            }
+            "get" | "g" => {
+                let address = parse_address(args.first().ok_or("Usage: get <address> [count]")?)?;
+                let count: i64 = match args.get(1) {
+                    Some(count) => parse_value(count)?,
+                    None => 1,
+                };
+                if !(1..=REPL_MAX_CELLS).contains(&count) {
+                    return Err(format!("Count must be between 1 and {}", REPL_MAX_CELLS));
+                }
+                let end = address
+                    .checked_add(count)
+                    .ok_or_else(|| format!("Invalid address: {}", address))?;
+                let values: Vec<String> = (address..end)
+                    .map(|address| self.computer.peek(address).unwrap().to_string())
+                    .collect();
+                format!("{}: {}", address, values.join(","))
+            }
+            "set" | "s" => {
+                if args.len() != 2 {
+                    return Err("Usage: set <address> <value>".to_string());
+                }
+                let address = parse_address(args[0])?;
+                *self.computer.instruction(address) = parse_value(args[1])?;
+                format!("{}: {}", address, self.computer.instruction(address))
+            }
+            "state" => format!(
+                "ptr: {}, relative_base: {}, input: {:?}",
+                self.computer.ptr, self.computer.relative_base, self.computer.input
+            ),
+            "reset" => {
+                self.computer = Computer::new(self.program.clone());
+                "reset".to_string()
+            }
+            "load" => {
+                self.program = read_program(args.first().ok_or("Usage: load <file>")?)?;
+                self.computer = Computer::new(self.program.clone());
+                format!("loaded {} instructions", self.program.len())
+            }
+            "save" => {
+                let path = args.first().ok_or("Usage: save <file>")?;
+                std::fs::write(path, self.computer.snapshot())
+                    .map_err(|e| format!("{}: {}", path, e))?;
+                format!("saved {}", path)
+            }
+            "restore" => {
+                let path = args.first().ok_or("Usage: restore <file>")?;
+                let snapshot =
+                    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+                self.computer = Computer::from_snapshot(&snapshot)?;
+                format!("restored {}", path)
+            }
+            "quit" | "exit" | "q" => return Ok(None),
+            command => return Err(format!("Unknown command: {}", command)),
+        };
+        Ok(Some(message))
+    }
+}
+
+fn repl(program: Instructions, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+    let mut repl = Repl::new(program);
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        match repl.execute(&line?) {
+            Ok(Some(message)) if message.is_empty() => {}
+            Ok(Some(message)) => writeln!(output, "{}", message)?,
+            Ok(None) => return Ok(()),
+            Err(error) => writeln!(output, "error: {}", error)?,
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+const CORPUS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/corpus");
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct CorpusCase {
+    name: String,
+    program: Instructions,
+    input: Vec<i64>,
+    output: Option<Vec<i64>>,
+    instructions: Option<Instructions>,
+    memory: Vec<(i64, i64)>,
+    error: Option<String>,
+}
+
+fn parse_corpus(text: &str) -> Result<Vec<CorpusCase>, String> {
+    let mut blocks: Vec<Vec<(String, String)>> = vec![vec![]];
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.is_empty() {
+            if !blocks.last().unwrap().is_empty() {
+                blocks.push(vec![]);
+            }
+            continue;
+        }
+        let block = blocks.last_mut().unwrap();
+        if line.starts_with(char::is_whitespace) && !block.is_empty() {
+            block.last_mut().unwrap().1 += trimmed;
+            continue;
+        }
+        let mut parts = trimmed.splitn(2, ':');
+        let key = parts.next().unwrap().trim().to_string();
+        let value = parts
+            .next()
+            .ok_or_else(|| format!("Missing ':' in line: {}", trimmed))?
+            .trim()
+            .to_string();
+        block.push((key, value));
+    }
+    blocks
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .enumerate()
+        .map(|(index, block)| {
+            let mut case = CorpusCase {
+                name: format!("case_{}", index + 1),
+                ..CorpusCase::default()
+            };
+            let mut has_program = false;
+            for (key, value) in block {
+                match key.as_str() {
+                    "name" => case.name = value,
+                    "program" => {
+                        case.program = parse_program(&value)?;
+                        has_program = true;
+                    }
+                    "input" => case.input = parse_program(&value)?,
+                    "output" => case.output = Some(parse_program(&value)?),
+                    "instructions" => case.instructions = Some(parse_program(&value)?),
+                    "memory" => case.memory.extend(parse_memory(&value)?),
+                    "error" => case.error = Some(value),
+                    _ => return Err(format!("{}: unknown key: {}", case.name, key)),
+                }
+            }
+            if !has_program {
+                return Err(format!("{}: missing program", case.name));
+            }
+            Ok(case)
+        })
+        .collect()
+}
+
+fn error_name(error: ComputerError) -> &'static str {
+    match error {
+        ComputerError::Overflow { .. } => "overflow",
+        ComputerError::MissingInput { .. } => "missing-input",
+        ComputerError::UnknownOpcode { .. } => "unknown-opcode",
+        ComputerError::UnknownMode { .. } => "unknown-mode",
+        ComputerError::NegativeAddress { .. } => "negative-address",
+        ComputerError::ValueOutOfRange { .. } => "value-out-of-range",
+    }
+}
+
+fn run_corpus_case(case: &CorpusCase) -> Result<(), String> {
+    let mut computer = Computer::new(case.program.clone());
+    for input in case.input.iter() {
+        computer.add_input(*input);
+    }
+    let (outputs, end) = computer.drain_outputs();
+    let error = match end {
+        ComputerExecution::Halt => None,
+        ComputerExecution::NeedInput => Some(ComputerError::MissingInput { ptr: computer.ptr }),
+        ComputerExecution::Error(error) => Some(error),
+        ComputerExecution::Yield(_) => unreachable!(),
+    };
+    let error = error.map(error_name);
+    if error != case.error.as_deref() {
+        return Err(format!(
+            "expected error {:?}, got {:?}",
+            case.error.as_deref().unwrap_or("none"),
+            error.unwrap_or("none")
+        ));
+    }
+    if let Some(expected) = case.output.as_ref() {
+        if *expected != outputs {
+            return Err(format!("expected output {:?}, got {:?}", expected, outputs));
+        }
+    }
+    if let Some(expected) = case.instructions.as_ref() {
+        if *expected != computer.instructions {
+            return Err(format!(
+                "expected instructions {}, got {}",
+                join(expected.iter()),
+                join(computer.instructions.iter())
+            ));
+        }
+    }
+    for (address, expected) in case.memory.iter() {
+        let actual = computer
+            .peek(*address)
+            .ok_or_else(|| format!("invalid memory address {}", address))?;
+        if actual != *expected {
+            return Err(format!(
+                "expected memory {}={}, got {}",
+                address, expected, actual
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn run_corpus_dir(dir: &str) -> Result<(usize, Vec<String>), String> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("{}: {}", dir, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some("intcode".as_ref()))
+        .collect();
+    paths.sort();
+    let mut passed = 0;
+    let mut failures = vec![];
+    for path in paths {
+        let file = path.display().to_string();
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", file, e))?;
+        let cases = parse_corpus(&text).map_err(|e| format!("{}: {}", file, e))?;
+        for case in cases.iter() {
+            match run_corpus_case(case) {
+                Ok(()) => passed += 1,
+                Err(reason) => failures.push(format!("{}: {}: {}", file, case.name, reason)),
+            }
+        }
+    }
+    Ok((passed, failures))
+}
+
+fn instruction_length(de: u16) -> i64 {
+    match de {
+        1 | 2 | 7 | 8 => 4,
+        5 | 6 => 3,
+        3 | 4 | 9 => 2,
+        _ => 1,
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+struct CellAccess {
+    reads: usize,
+    writes: usize,
+    executions: usize,
+}
+
+impl CellAccess {
+    fn kind(self) -> char {
+        if self.executions > 0 {
+            'X'
+        } else if self.writes > 0 {
+            'W'
+        } else if self.reads > 0 {
+            'R'
+        } else {
+            '.'
+        }
+    }
+
+    fn total(self) -> usize {
+        self.reads + self.writes + self.executions
+    }
+
+    fn rgb(self, max: usize) -> (u8, u8, u8) {
+        if self.total() == 0 {
+            return (40, 40, 40);
+        }
+        (
+            shade(self.reads, max),
+            shade(self.executions, max),
+            shade(self.writes, max),
+        )
+    }
+}
+
+fn shade(count: usize, max: usize) -> u8 {
+    if count == 0 {
+        return 0;
+    }
+    let ratio = ((count as f64).ln() + 1.0) / ((max as f64).ln() + 1.0);
+    (80.0 + ratio * 175.0).min(255.0) as u8
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Region {
+    Instructions,
+    Memory,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct MemoryCell<W> {
+    address: i64,
+    value: W,
+    access: CellAccess,
+}
+
+struct MemoryRow<W> {
+    region: Region,
+    start: i64,
+    cells: Vec<Option<MemoryCell<W>>>,
+}
+
+struct MemoryMap<W> {
+    instructions: Vec<MemoryCell<W>>,
+    memory: Vec<MemoryCell<W>>,
+}
+
+impl<W: Word> Computer<W> {
+    pub fn memory_map(&self) -> MemoryMap<W> {
+        let empty = HashMap::new();
+        let trace = self.trace.as_ref().unwrap_or(&empty);
+        let cell = |address: i64, value: W| MemoryCell {
+            address,
+            value,
+            access: trace.get(&address).copied().unwrap_or_default(),
+        };
+        let instructions = self
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(address, value)| cell(address as i64, value.clone()))
+            .collect();
+        let mut memory: Vec<_> = self
+            .memory
+            .iter()
+            .map(|(address, value)| cell(*address, value.clone()))
+            .collect();
+        memory.sort_by_key(|cell| cell.address);
+        MemoryMap {
+            instructions,
+            memory,
+        }
+    }
+}
+
+impl<W: Word> MemoryMap<W> {
+    fn rows(&self, columns: usize) -> Vec<MemoryRow<W>> {
+        let columns = columns.max(1) as i64;
+        let mut rows: Vec<MemoryRow<W>> = vec![];
+        let regions = [
+            (Region::Instructions, &self.instructions),
+            (Region::Memory, &self.memory),
+        ];
+        for (region, cells) in regions.iter() {
+            for cell in cells.iter() {
+                let start = cell.address - cell.address.rem_euclid(columns);
+                let same_row = match rows.last() {
+                    Some(row) => row.region == *region && row.start == start,
+                    None => false,
+                };
+                if !same_row {
+                    rows.push(MemoryRow {
+                        region: *region,
+                        start,
+                        cells: vec![None; columns as usize],
+                    });
+                }
+                let row = rows.last_mut().unwrap();
+                row.cells[(cell.address - start) as usize] = Some(cell.clone());
+            }
+        }
+        rows
+    }
+
+    fn max_count(&self) -> usize {
+        self.instructions
+            .iter()
+            .chain(self.memory.iter())
+            .flat_map(|cell| {
+                let access = cell.access;
+                vec![access.reads, access.writes, access.executions]
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    pub fn to_text(&self, columns: usize) -> String {
+        let mut text = String::new();
+        let mut region = None;
+        for row in self.rows(columns) {
+            if region != Some(row.region) {
+                region = Some(row.region);
+                text += &match row.region {
+                    Region::Instructions => {
+                        format!("instructions [0, {})\n", self.instructions.len())
+                    }
+                    Region::Memory => format!("memory ({} cells)\n", self.memory.len()),
+                };
+            }
+            text += &format!("{:>8} |", row.start);
+            for cell in row.cells.iter() {
+                text.push(match cell {
+                    Some(cell) => cell.access.kind(),
+                    None => ' ',
+                });
+            }
+            text += "|\n";
+        }
+        text
+    }
+
+    pub fn to_ppm(&self, columns: usize) -> String {
+        let max = self.max_count();
+        let rows = self.rows(columns);
+        let mut ppm = format!("P3\n{} {}\n255\n", columns.max(1), rows.len());
+        for row in rows.iter() {
+            let pixels = row
+                .cells
+                .iter()
+                .map(|cell| {
+                    let (r, g, b) = match cell {
+                        Some(cell) => cell.access.rgb(max),
+                        None => (0, 0, 0),
+                    };
+                    format!("{} {} {}", r, g, b)
+                })
+                .collect::<Vec<_>>();
+            ppm += &pixels.join(" ");
+            ppm.push('\n');
+        }
+        ppm
+    }
+
+    pub fn to_html(&self, columns: usize) -> String {
+        let max = self.max_count();
+        let mut html = String::from("<table class=\"memory-map\">\n");
+        let mut region = None;
+        for row in self.rows(columns) {
+            if region != Some(row.region) {
+                region = Some(row.region);
+                html += &format!(
+                    "<tr><th colspan=\"{}\">{}</th></tr>\n",
+                    columns.max(1) + 1,
+                    match row.region {
+                        Region::Instructions => "instructions",
+                        Region::Memory => "memory",
+                    }
+                );
+            }
+            html += &format!("<tr><th>{}</th>", row.start);
+            for cell in row.cells.iter() {
+                match cell {
+                    Some(cell) => {
+                        let (r, g, b) = cell.access.rgb(max);
+                        html += &format!(
+                            "<td style=\"background:#{:02x}{:02x}{:02x}\" title=\"{}: r={} w={} x={}\">{}</td>",
+                            r,
+                            g,
+                            b,
+                            cell.address,
+                            cell.access.reads,
+                            cell.access.writes,
+                            cell.access.executions,
+                            cell.value
+                        );
+                    }
+                    None => html += "<td></td>",
+                }
+            }
+            html += "</tr>\n";
+        }
+        html += "</table>\n";
+        html
     }
 }
 
@@ -215,13 +1242,78 @@ mod test {
 
     fn computer_n(instructions: Instructions, input: i64) -> ComputerResult {
         let mut computer = Computer::new(instructions).with_input(input);
-        let outputs = computer.run();
+        let outputs = computer.run().unwrap();
         ComputerResult { computer, outputs }
     }
+
+    fn run_checked<W: Word>(instructions: Instructions<W>) -> Result<Vec<W>, ComputerError> {
+        Computer::new(instructions).run()
+    }
+
+    fn traced(instructions: Instructions, input: i64) -> MemoryMap<i64> {
+        let mut computer = Computer::new(instructions).with_input(input).with_trace();
+        computer.run().unwrap();
+        computer.memory_map()
+    }
+
+    fn access(reads: usize, writes: usize, executions: usize) -> CellAccess {
+        CellAccess {
+            reads,
+            writes,
+            executions,
+        }
+    }
+
+    fn repl_session(program: Instructions, commands: &str) -> String {
+        let mut output = vec![];
+        repl(program, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn repl_execute(
+        program: Instructions,
+        commands: &[&str],
+    ) -> Vec<Result<Option<String>, String>> {
+        let mut repl = Repl::new(program);
+        commands
+            .iter()
+            .map(|command| repl.execute(command))
+            .collect()
+    }
+
+    fn snapshot_round_trip(instructions: Instructions, input: i64) -> String {
+        let mut computer = Computer::new(instructions).with_input(input);
+        computer.next_output();
+        Computer::<i64>::from_snapshot(&computer.snapshot())
+            .unwrap()
+            .snapshot()
+    }
+
+    #[test]
+    fn corpus() {
+        let (passed, failures) = run_corpus_dir(CORPUS_DIR).unwrap();
+        assert_eq!(failures, Vec::<String>::new());
+        assert!(passed > 0);
+    }
+
+    fn corpus_case(text: &str) -> Result<(), String> {
+        run_corpus_case(&parse_corpus(text)?[0])
+    }
+
+    fn run_bignum(program: &str) -> Result<String, ComputerError> {
+        Computer::new(parse_program::<BigInt>(program).unwrap())
+            .run()
+            .map(|outputs| join(outputs.iter()))
+    }
+
+    fn widen(instructions: Instructions) -> Instructions<i128> {
+        instructions.into_iter().map(i128::from).collect()
+    }
     fn get_value(instructions: Instructions, mode: u16, value: i64) -> i64 {
-        *Computer::new(instructions)
+        Computer::new(instructions)
             .with_input(0)
-            .access(mode, value)
+            .read(mode, value)
+            .unwrap()
     }
 
     fn stringify(program: Instructions) -> String {
@@ -270,6 +1362,87 @@ mod test {
         relative_mode_example_3: computer_n(vec![104,1125899906842624,99], 42).outputs[0] => 1125899906842624;
 
         op_203_base_1000_input_42_eq_42: computer_n(vec![109, 1000, 203, 5, 4, 1005, 99], 42).outputs[0] => 42;
+
+        relative_mode_example_2_i128: run_checked(widen(vec![1102,34915192,34915192,7,4,7,99,0])) => Ok(vec![1219070632396864]);
+        relative_mode_example_2_i128_eq_i64: run_checked(widen(vec![1102,34915192,34915192,7,4,7,99,0])).unwrap()[0] => i128::from(computer_n(vec![1102,34915192,34915192,7,4,7,99,0], 42).outputs[0]);
+        overflow_mul_i64: run_checked::<i64>(vec![1102,4294967296,4294967296,7,4,7,99,0]) => Err(ComputerError::Overflow { ptr: 0, opcode: 2 });
+        overflow_add_i64: run_checked::<i64>(vec![4,0,1101,9223372036854775807,1,9,4,9,99,0]) => Err(ComputerError::Overflow { ptr: 2, opcode: 1 });
+        overflow_mul_i128: run_checked(widen(vec![1102,4294967296,4294967296,7,4,7,99,0])) => Ok(vec![18446744073709551616]);
+        overflow_add_i128: run_checked(widen(vec![1101,9223372036854775807,1,7,4,7,99,0])) => Ok(vec![9223372036854775808]);
+        overflow_keeps_outputs_before_error: Computer::<i64>::new(vec![4,0,1101,9223372036854775807,1,9,4,9,99,0]).drain_outputs() => (vec![4], ComputerExecution::Error(ComputerError::Overflow { ptr: 2, opcode: 1 }));
+        drain_outputs_until_need_input: Computer::<i64>::new(vec![104, 7, 3, 0, 99]).drain_outputs() => (vec![7], ComputerExecution::NeedInput);
+        drain_outputs_until_halt: Computer::<i64>::new(vec![104, 7, 104, 8, 99]).drain_outputs() => (vec![7, 8], ComputerExecution::Halt);
+        relative_mode_example_2_bignum: run_bignum("1102,34915192,34915192,7,4,7,99,0") => Ok("1219070632396864".to_string());
+        bignum_beyond_i128: run_bignum("1102,18446744073709551616,18446744073709551616,7,4,7,99,0") => Ok("340282366920938463463374607431768211456".to_string());
+        bignum_repeated_squaring: run_bignum("1102,340282366920938463463374607431768211456,340282366920938463463374607431768211456,7,4,7,99,0") => Ok("115792089237316195423570985008687907853269984665640564039457584007913129639936".to_string());
+        bignum_add_mixed_signs: run_bignum("1101,-5,3,13,4,13,1101,5,-3,14,4,14,99,0,0") => Ok("-2,2".to_string());
+        bignum_add_to_zero: run_bignum("1101,-18446744073709551616,18446744073709551616,7,4,7,99,0") => Ok("0".to_string());
+        bignum_mul_signs: run_bignum("1102,-4294967296,4294967296,13,4,13,1102,-3,-3,14,4,14,99,0,0") => Ok("-18446744073709551616,9".to_string());
+        bignum_less_than: run_bignum("1107,-10,-3,13,4,13,1107,-3,-10,14,4,14,99,0,0") => Ok("1,0".to_string());
+        bignum_equals: run_bignum("1108,-4294967296,-4294967296,7,4,7,99,0") => Ok("1".to_string());
+        bignum_address_out_of_range: BigInt::from_str("9223372036854775808").unwrap().to_i64() => None;
+        bignum_i64_extremes: (BigInt::from_i64(i64::MIN).to_i64(), BigInt::from_i64(i64::MAX).to_i64(), BigInt::from_i64(-1).to_string()) => (Some(i64::MIN), Some(i64::MAX), "-1".to_string());
+        bignum_parse_display: ["-123456789012345678901234567890", "0", "-0", "+7", "1000000000"].iter().map(|text| BigInt::from_str(text).unwrap().to_string()).collect::<Vec<_>>() => vec!["-123456789012345678901234567890", "0", "0", "7", "1000000000"];
+        bignum_parse_invalid: (BigInt::from_str("1x").is_err(), BigInt::from_str("-").is_err(), BigInt::from_str("").is_err()) => (true, true, true);
+        puzzle_part_1_bignum_eq_i64: Computer::new(PUZZLE_INPUT.iter().map(|value| BigInt::from_i64(*value)).collect()).with_input(BigInt::from_i64(1)).run().map(|outputs| join(outputs.iter())) => Ok(join(computer_one(PUZZLE_INPUT.to_vec()).outputs.iter()));
+        memory_map_instruction_cells: traced(vec![1, 0, 0, 0, 99], 0).instructions.into_iter().map(|cell| cell.access).collect::<Vec<_>>() => vec![access(2, 1, 1), access(0, 0, 1), access(0, 0, 1), access(0, 0, 1), access(0, 0, 1)];
+        memory_map_extended_cells: traced(vec![109, 1000, 203, 5, 4, 1005, 99], 42).memory => vec![MemoryCell { address: 1005, value: 42, access: access(1, 1, 0) }];
+        memory_map_without_trace: Computer::<i64>::new(vec![1, 0, 0, 0, 99]).memory_map().instructions[0].access => access(0, 0, 0);
+        memory_map_text: traced(vec![1, 0, 0, 0, 99, 7], 0).to_text(4) => "instructions [0, 6)\n       0 |XXXX|\n       4 |X.  |\n";
+        memory_map_text_extended: traced(vec![109, 1000, 203, 5, 4, 1005, 99], 42).to_text(4) => "instructions [0, 7)\n       0 |XXXX|\n       4 |XXX |\nmemory (1 cells)\n    1004 | W  |\n";
+        memory_map_ppm_header: traced(vec![1, 0, 0, 0, 99, 7], 0).to_ppm(4).lines().take(3).collect::<Vec<_>>() => vec!["P3", "4 2", "255"];
+        memory_map_ppm_untouched_and_absent: traced(vec![1, 0, 0, 0, 99, 7], 0).to_ppm(4).lines().nth(4) => Some("0 183 0 40 40 40 0 0 0 0 0 0");
+        memory_map_html_rows: traced(vec![1, 0, 0, 0, 99, 7], 0).to_html(4).matches("<tr>").count() => 3;
+        memory_map_html_title: traced(vec![1, 0, 0, 0, 99, 7], 0).to_html(4).contains("title=\"0: r=2 w=1 x=1\">2</td>") => true;
+        need_input_without_input: Computer::<i64>::new(vec![3, 0, 4, 0, 99]).next_output() => ComputerExecution::NeedInput;
+        need_input_does_not_advance: Computer::<i64>::new(vec![3, 0, 4, 0, 99]).run() => Err(ComputerError::MissingInput { ptr: 0 });
+        parse_program_1: parse_program::<i64>("1, 0,0,0,99\n") => Ok(vec![1, 0, 0, 0, 99]);
+        parse_program_invalid: parse_program::<i64>("1,x,99") => Err("Invalid number: x".to_string());
+        snapshot_1: Computer::<i64>::new(vec![109, 1000, 203, 5, 4, 1005, 99]).with_input(42).with_input(7).snapshot() => "ptr 0\nrelative_base 0\ninput 42,7\ninstructions 109,1000,203,5,4,1005,99\nmemory \n";
+        snapshot_round_trip_1: snapshot_round_trip(vec![109, 1000, 203, 5, 4, 1005, 99], 42) => "ptr 6\nrelative_base 1000\ninput \ninstructions 109,1000,203,5,4,1005,99\nmemory 1005=42\n";
+        snapshot_unknown_entry: Computer::<i64>::from_snapshot("foo 1").is_err() => true;
+        repl_session_1: repl_session(vec![3, 0, 4, 0, 99], "run\ninput 5\nrun\nquit\n") => "> outputs: []\nneed input\n> input: [5]\n> outputs: [5]\nhalt\n> ";
+        repl_session_next: repl_session(vec![104, 1, 104, 2, 99], "next\nn\nn\n") => "> output: 1\n> output: 2\n> halt\n> ";
+        repl_get_set: repl_execute(vec![1, 0, 0, 0, 99], &["set 0 2", "get 0 3", "reset", "get 0"]) => vec![Ok(Some("0: 2".to_string())), Ok(Some("0: 2,0,0".to_string())), Ok(Some("reset".to_string())), Ok(Some("0: 1".to_string()))];
+        repl_state: repl_execute(vec![109, 1000, 3, 0, 99], &["n", "state"]) => vec![Ok(Some("need input".to_string())), Ok(Some("ptr: 2, relative_base: 1000, input: []".to_string()))];
+        vm_unknown_opcode: run_checked::<i64>(vec![1, 0, 0, 0, 42]) => Err(ComputerError::UnknownOpcode { ptr: 4, opcode: 42 });
+        vm_unknown_mode: run_checked::<i64>(vec![301, 0, 0, 0, 99]) => Err(ComputerError::UnknownMode { ptr: 0, mode: 3 });
+        vm_negative_address: run_checked::<i64>(vec![1, -1, 0, 0, 99]) => Err(ComputerError::NegativeAddress { ptr: 0, address: -1 });
+        vm_negative_relative_address: run_checked::<i64>(vec![109, -5, 204, 0, 99]) => Err(ComputerError::NegativeAddress { ptr: 2, address: -5 });
+        vm_negative_jump: run_checked::<i64>(vec![1105, 1, -3, 99]) => Err(ComputerError::NegativeAddress { ptr: 0, address: -3 });
+        vm_jump_to_max_address: run_checked::<i64>(vec![1105, 1, i64::MAX]) => Err(ComputerError::UnknownOpcode { ptr: i64::MAX, opcode: 0 });
+        vm_jump_to_max_address_traced: Computer::new(vec![1105, 1, i64::MAX]).with_trace().run() => Err(ComputerError::UnknownOpcode { ptr: i64::MAX, opcode: 0 });
+        vm_jump_to_max_address_with_parameters: run_checked::<i64>(vec![1101, 104, 0, i64::MAX, 1105, 1, i64::MAX]) => Err(ComputerError::ValueOutOfRange { ptr: i64::MAX });
+        vm_instruction_ends_at_max_address: run_checked::<i64>(vec![1101, 104, 0, i64::MAX - 1, 1105, 1, i64::MAX - 1]) => Err(ComputerError::ValueOutOfRange { ptr: i64::MAX - 1 });
+        vm_instruction_ends_at_max_address_traced: Computer::new(vec![1101, 104, 0, i64::MAX - 1, 1105, 1, i64::MAX - 1]).with_trace().run() => Err(ComputerError::ValueOutOfRange { ptr: i64::MAX - 1 });
+        vm_parameter_past_max_address: { let mut computer = Computer::new(vec![99i64]); computer.ptr = i64::MAX; computer.address(1, 1) } => Err(ComputerError::ValueOutOfRange { ptr: i64::MAX });
+        vm_value_out_of_range: run_bignum("4,18446744073709551616,99") => Err(ComputerError::ValueOutOfRange { ptr: 0 });
+        vm_error_keeps_outputs: Computer::<i64>::new(vec![104, 1, 42]).drain_outputs() => (vec![1], ComputerExecution::Error(ComputerError::UnknownOpcode { ptr: 2, opcode: 42 }));
+        peek_without_side_effects: { let computer = Computer::<i64>::new(vec![1, 2]); (computer.peek(1), computer.peek(5000), computer.peek(-1), computer.memory.len()) } => (Some(2), Some(0), None, 0);
+        repl_get_does_not_touch_memory: { let mut repl = Repl::new(vec![99]); let result = repl.execute("get 1000 3"); (result, repl.computer.memory.len()) } => (Ok(Some("1000: 0,0,0".to_string())), 0);
+        repl_invalid_addresses: repl_execute(vec![99], &["get -1", "set -2 5", "get 0 0", "get 0 1025", "get 9223372036854775807 2"]) => vec![Err("Invalid address: -1".to_string()), Err("Invalid address: -2".to_string()), Err("Count must be between 1 and 1024".to_string()), Err("Count must be between 1 and 1024".to_string()), Err("Invalid address: 9223372036854775807".to_string())];
+        repl_vm_errors: repl_execute(vec![104, 1, 42], &["next", "next", "reset", "run"]) => vec![Ok(Some("output: 1".to_string())), Err("UnknownOpcode { ptr: 2, opcode: 42 }".to_string()), Ok(Some("reset".to_string())), Err("UnknownOpcode { ptr: 2, opcode: 42 } after outputs [1]".to_string())];
+        repl_errors: repl_execute(vec![99], &["input", "set 1", "get x", "frobnicate", "quit"]) => vec![Err("Usage: input <n>...".to_string()), Err("Usage: set <address> <value>".to_string()), Err("Invalid number: x".to_string()), Err("Unknown command: frobnicate".to_string()), Ok(None)];
+        parse_corpus_1: parse_corpus("# comment\nname: add\nprogram: 1,0,0,0,\n  99\ninput: 1, 2\noutput:\ninstructions: 2,0,0,0,99\nmemory: 0=2, 1000=0\n\nprogram: 99\nerror: overflow\n") => Ok(vec![
+            CorpusCase { name: "add".to_string(), program: vec![1, 0, 0, 0, 99], input: vec![1, 2], output: Some(vec![]), instructions: Some(vec![2, 0, 0, 0, 99]), memory: vec![(0, 2), (1000, 0)], error: None },
+            CorpusCase { name: "case_2".to_string(), program: vec![99], error: Some("overflow".to_string()), ..CorpusCase::default() },
+        ]);
+        parse_corpus_missing_program: parse_corpus("name: empty\ninput: 1") => Err("empty: missing program".to_string());
+        parse_corpus_unknown_key: parse_corpus("program: 99\nfoo: 1") => Err("case_1: unknown key: foo".to_string());
+        parse_corpus_missing_colon: parse_corpus("program 99") => Err("Missing ':' in line: program 99".to_string());
+        corpus_case_passes: corpus_case("program: 3,0,4,0,99\ninput: 5\noutput: 5\nmemory: 0=5") => Ok(());
+        corpus_case_wrong_output: corpus_case("program: 104,1,99\noutput: 2") => Err("expected output [2], got [1]".to_string());
+        corpus_case_wrong_memory: corpus_case("program: 1,0,0,0,99\nmemory: 0=3") => Err("expected memory 0=3, got 2".to_string());
+        corpus_case_wrong_instructions: corpus_case("program: 1,0,0,0,99\ninstructions: 1,0,0,0,99") => Err("expected instructions 1,0,0,0,99, got 2,0,0,0,99".to_string());
+        corpus_case_unexpected_error: corpus_case("program: 3,0,99") => Err("expected error \"none\", got \"missing-input\"".to_string());
+        corpus_case_unknown_opcode: corpus_case("program: 104,1,42\noutput: 1\nerror: unknown-opcode") => Ok(());
+        corpus_case_vm_error_is_a_failure: corpus_case("program: 1,-1,0,0,99") => Err("expected error \"none\", got \"negative-address\"".to_string());
+        corpus_case_invalid_memory_address: corpus_case("program: 99\nmemory: -1=0") => Err("invalid memory address -1".to_string());
+        corpus_dir_records_vm_errors: { let dir = std::env::temp_dir().join(format!("day9_corpus_{}", std::process::id())); std::fs::create_dir_all(&dir).unwrap(); std::fs::write(dir.join("faults.intcode"), "name: bad_opcode\nprogram: 42\n\nname: fine\nprogram: 99\n").unwrap(); let (passed, failures) = run_corpus_dir(dir.to_str().unwrap()).unwrap(); std::fs::remove_dir_all(&dir).unwrap(); (passed, failures.len()) } => (1, 1);
+        parse_memory_cells: parse_memory::<i64>("0=2, 1000=-3,") => Ok(vec![(0, 2), (1000, -3)]);
+        parse_memory_invalid: parse_memory::<i64>("0=x") => Err("Invalid number: x".to_string());
+        corpus_case_expected_error: corpus_case("program: 3,0,99\nerror: missing-input") => Ok(());
+        puzzle_part_1_i128_eq_i64: Computer::new(widen(PUZZLE_INPUT.to_vec())).with_input(1).run() => Ok(widen(computer_one(PUZZLE_INPUT.to_vec()).outputs));
     }
 }
 